@@ -0,0 +1,163 @@
+use crate::nes::Region;
+
+/// The timer periods in CPU cycles.
+const NTSC_RATES: [u16; 16] = [
+    428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
+];
+const PAL_RATES: [u16; 16] = [
+    398, 354, 316, 298, 276, 236, 210, 198, 176, 148, 132, 118, 98, 78, 66, 50,
+];
+
+/// The delta modulation channel at `$4010-$4013`, which plays 1 bit delta
+/// encoded samples that it fetches from memory by DMA.
+#[derive(Debug)]
+pub struct Dmc {
+    rates: &'static [u16; 16],
+    period: u16,
+    timer: u16,
+    irq_enabled: bool,
+    looping: bool,
+    pub irq: bool,
+    level: u8,
+
+    sample_addr: u16,
+    sample_len: u16,
+    /// The address of the next byte to fetch.
+    current_addr: u16,
+    bytes_remaining: u16,
+    /// The byte that was fetched and waits for the output unit.
+    buffer: Option<u8>,
+
+    shift: u8,
+    bits_remaining: u8,
+    silence: bool,
+}
+
+impl Default for Dmc {
+    fn default() -> Self {
+        Self {
+            rates: &NTSC_RATES,
+            period: NTSC_RATES[0],
+            timer: 0,
+            irq_enabled: false,
+            looping: false,
+            irq: false,
+            level: 0,
+            sample_addr: 0xC000,
+            sample_len: 1,
+            current_addr: 0xC000,
+            bytes_remaining: 0,
+            buffer: None,
+            shift: 0,
+            bits_remaining: 8,
+            silence: true,
+        }
+    }
+}
+
+impl Dmc {
+    pub fn set_region(&mut self, region: Region) {
+        self.rates = match region {
+            Region::Ntsc => &NTSC_RATES,
+            Region::Pal => &PAL_RATES,
+        };
+    }
+
+    /// Writes one of the four registers, `reg` is the offset from the first one.
+    pub fn write(&mut self, reg: u16, val: u8) {
+        match reg {
+            0 => {
+                self.irq_enabled = val & 0x80 != 0;
+                if !self.irq_enabled {
+                    self.irq = false;
+                }
+                self.looping = val & 0x40 != 0;
+                self.period = self.rates[(val & 0x0F) as usize];
+            }
+            1 => self.level = val & 0x7F,
+            2 => self.sample_addr = 0xC000 | (val as u16) << 6,
+            _ => self.sample_len = ((val as u16) << 4) + 1,
+        }
+    }
+
+    /// Handles bit 4 of `$4015`. Enabling the channel restarts the sample
+    /// only if it has finished.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.irq = false;
+        if !enabled {
+            self.bytes_remaining = 0;
+        } else if self.bytes_remaining == 0 {
+            self.restart();
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.bytes_remaining > 0
+    }
+
+    fn restart(&mut self) {
+        self.current_addr = self.sample_addr;
+        self.bytes_remaining = self.sample_len;
+    }
+
+    /// The address the channel wants to read, once its buffer is empty.
+    pub fn dma_request(&self) -> Option<u16> {
+        match self.buffer {
+            None if self.bytes_remaining > 0 => Some(self.current_addr),
+            _ => None,
+        }
+    }
+
+    /// Fills the buffer with the byte the DMA read.
+    pub fn dma_complete(&mut self, val: u8) {
+        self.buffer = Some(val);
+        self.current_addr = match self.current_addr {
+            0xFFFF => 0x8000,
+            addr => addr + 1,
+        };
+        self.bytes_remaining -= 1;
+        if self.bytes_remaining == 0 {
+            if self.looping {
+                self.restart();
+            } else if self.irq_enabled {
+                self.irq = true;
+            }
+        }
+    }
+
+    /// Clocked on every CPU cycle.
+    pub fn clock_timer(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+            return;
+        }
+        self.timer = self.period - 1;
+
+        if !self.silence {
+            if self.shift & 0x01 != 0 {
+                if self.level <= 125 {
+                    self.level += 2;
+                }
+            } else if self.level >= 2 {
+                self.level -= 2;
+            }
+        }
+        self.shift >>= 1;
+
+        self.bits_remaining -= 1;
+        if self.bits_remaining == 0 {
+            self.bits_remaining = 8;
+            match self.buffer.take() {
+                Some(val) => {
+                    self.shift = val;
+                    self.silence = false;
+                }
+                None => self.silence = true,
+            }
+        }
+    }
+
+    pub fn output(&self) -> u8 {
+        self.level
+    }
+}
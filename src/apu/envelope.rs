@@ -0,0 +1,53 @@
+/// The volume envelope of the pulse and noise channels, which either
+/// outputs a constant volume or a sawtooth that decays from 15 to 0.
+#[derive(Debug, Default)]
+pub struct Envelope {
+    /// Set by a write to the length register, restarts the decay on the
+    /// next quarter frame.
+    start: bool,
+    looping: bool,
+    constant: bool,
+    /// The constant volume, or the period of the divider.
+    volume: u8,
+    divider: u8,
+    decay: u8,
+}
+
+impl Envelope {
+    /// Handles the `--LC VVVV` bits of the first register of a channel.
+    pub fn write(&mut self, val: u8) {
+        self.looping = val & 0x20 != 0;
+        self.constant = val & 0x10 != 0;
+        self.volume = val & 0x0F;
+    }
+
+    pub fn restart(&mut self) {
+        self.start = true;
+    }
+
+    /// Clocked by the frame counter on every quarter frame.
+    pub fn clock(&mut self) {
+        if self.start {
+            self.start = false;
+            self.decay = 15;
+            self.divider = self.volume;
+        } else if self.divider == 0 {
+            self.divider = self.volume;
+            if self.decay > 0 {
+                self.decay -= 1;
+            } else if self.looping {
+                self.decay = 15;
+            }
+        } else {
+            self.divider -= 1;
+        }
+    }
+
+    pub fn output(&self) -> u8 {
+        if self.constant {
+            self.volume
+        } else {
+            self.decay
+        }
+    }
+}
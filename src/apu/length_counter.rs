@@ -0,0 +1,41 @@
+/// The lengths a write to the length register can load, indexed by its upper 5 bits.
+const LENGTHS: [u8; 32] = [
+    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14, 12, 16, 24, 18, 48, 20, 96, 22,
+    192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+/// Silences a channel once it runs out, unless it's halted.
+#[derive(Debug, Default)]
+pub struct LengthCounter {
+    /// Controlled by `$4015`, a disabled channel can't be loaded.
+    enabled: bool,
+    pub halt: bool,
+    counter: u8,
+}
+
+impl LengthCounter {
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.counter = 0;
+        }
+    }
+
+    /// Handles the `LLLL L---` bits of the length register of a channel.
+    pub fn load(&mut self, val: u8) {
+        if self.enabled {
+            self.counter = LENGTHS[(val >> 3) as usize];
+        }
+    }
+
+    /// Clocked by the frame counter on every half frame.
+    pub fn clock(&mut self) {
+        if self.counter > 0 && !self.halt {
+            self.counter -= 1;
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.counter > 0
+    }
+}
@@ -0,0 +1,262 @@
+//! The audio processing unit of the 2A03, which generates the sound of the
+//! console from its channels.
+
+mod dmc;
+mod envelope;
+mod length_counter;
+mod noise;
+mod pulse;
+mod triangle;
+
+use crate::nes::Region;
+use dmc::Dmc;
+use noise::Noise;
+use pulse::Pulse;
+use triangle::Triangle;
+
+/// The CPU cycles at which the frame counter clocks the channels. The fourth
+/// step is the last one of the 4-step sequence, the fifth one only happens
+/// in the 5-step sequence.
+const NTSC_STEPS: [u32; 5] = [7457, 14913, 22371, 29829, 37281];
+const PAL_STEPS: [u32; 5] = [8313, 16627, 24939, 33253, 41565];
+
+/// The sound channels of the APU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    Pulse1,
+    Pulse2,
+    Triangle,
+    Noise,
+    Dmc,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 5] = [
+        Channel::Pulse1,
+        Channel::Pulse2,
+        Channel::Triangle,
+        Channel::Noise,
+        Channel::Dmc,
+    ];
+
+    /// A short lowercase name, like `pulse1`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Channel::Pulse1 => "pulse1",
+            Channel::Pulse2 => "pulse2",
+            Channel::Triangle => "triangle",
+            Channel::Noise => "noise",
+            Channel::Dmc => "dmc",
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Apu {
+    pulse1: Pulse,
+    pulse2: Pulse,
+    triangle: Triangle,
+    noise: Noise,
+    dmc: Dmc,
+
+    steps: &'static [u32; 5],
+    /// The number of CPU cycles since the start of the frame counter sequence.
+    frame_cycle: u32,
+    five_step: bool,
+    irq_inhibit: bool,
+    frame_irq: bool,
+    /// A write to `$4017` resets the sequence a few cycles later, this holds
+    /// the number of cycles left.
+    frame_reset_delay: u8,
+    /// Counts CPU cycles, the pulse and noise timers only run on every
+    /// other one.
+    cycle: u64,
+}
+
+impl Default for Apu {
+    fn default() -> Self {
+        Self {
+            pulse1: Pulse::new(true),
+            pulse2: Pulse::new(false),
+            triangle: Triangle::default(),
+            noise: Noise::default(),
+            dmc: Dmc::default(),
+            steps: &NTSC_STEPS,
+            frame_cycle: 0,
+            five_step: false,
+            irq_inhibit: false,
+            frame_irq: false,
+            frame_reset_delay: 0,
+            cycle: 0,
+        }
+    }
+}
+
+impl Apu {
+    /// Selects the timing of the frame counter and the noise periods.
+    pub fn set_region(&mut self, region: Region) {
+        self.steps = match region {
+            Region::Ntsc => &NTSC_STEPS,
+            Region::Pal => &PAL_STEPS,
+        };
+        self.noise.set_region(region);
+        self.dmc.set_region(region);
+    }
+
+    /// Advances the APU by a single CPU cycle.
+    pub fn clock(&mut self) {
+        self.triangle.clock_timer();
+        if self.cycle % 2 == 1 {
+            self.pulse1.clock_timer();
+            self.pulse2.clock_timer();
+        }
+        self.noise.clock_timer();
+        self.dmc.clock_timer();
+        self.clock_frame_counter();
+        self.cycle += 1;
+    }
+
+    fn clock_frame_counter(&mut self) {
+        if self.frame_reset_delay > 0 {
+            self.frame_reset_delay -= 1;
+            if self.frame_reset_delay == 0 {
+                self.frame_cycle = 0;
+                if self.five_step {
+                    self.clock_quarter_frame();
+                    self.clock_half_frame();
+                }
+                return;
+            }
+        }
+
+        self.frame_cycle += 1;
+        let last = self.steps[3];
+        match self.frame_cycle {
+            c if c == self.steps[0] || c == self.steps[2] => self.clock_quarter_frame(),
+            c if c == self.steps[1] => {
+                self.clock_quarter_frame();
+                self.clock_half_frame();
+            }
+            c if self.five_step => {
+                if c == self.steps[4] {
+                    self.clock_quarter_frame();
+                    self.clock_half_frame();
+                } else if c == self.steps[4] + 1 {
+                    self.frame_cycle = 0;
+                }
+            }
+            // The 4-step sequence raises the IRQ flag over three cycles.
+            c if c == last - 1 => self.set_frame_irq(),
+            c if c == last => {
+                self.set_frame_irq();
+                self.clock_quarter_frame();
+                self.clock_half_frame();
+            }
+            c if c == last + 1 => {
+                self.set_frame_irq();
+                self.frame_cycle = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn set_frame_irq(&mut self) {
+        if !self.irq_inhibit {
+            self.frame_irq = true;
+        }
+    }
+
+    /// Clocks the envelopes and the linear counter.
+    fn clock_quarter_frame(&mut self) {
+        self.pulse1.clock_quarter_frame();
+        self.pulse2.clock_quarter_frame();
+        self.triangle.clock_quarter_frame();
+        self.noise.clock_quarter_frame();
+    }
+
+    /// Clocks the length counters and the sweep units.
+    fn clock_half_frame(&mut self) {
+        self.pulse1.clock_half_frame();
+        self.pulse2.clock_half_frame();
+        self.triangle.clock_half_frame();
+        self.noise.clock_half_frame();
+    }
+
+    /// Handles a write to `$4000-$4013`, `$4015` or `$4017`.
+    pub fn write_register(&mut self, addr: u16, val: u8) {
+        match addr {
+            0x4000..=0x4003 => self.pulse1.write(addr - 0x4000, val),
+            0x4004..=0x4007 => self.pulse2.write(addr - 0x4004, val),
+            0x4008..=0x400B => self.triangle.write(addr - 0x4008, val),
+            0x400C..=0x400F => self.noise.write(addr - 0x400C, val),
+            0x4010..=0x4013 => self.dmc.write(addr - 0x4010, val),
+            0x4015 => {
+                self.pulse1.length.set_enabled(val & 0x01 != 0);
+                self.pulse2.length.set_enabled(val & 0x02 != 0);
+                self.triangle.length.set_enabled(val & 0x04 != 0);
+                self.noise.length.set_enabled(val & 0x08 != 0);
+                self.dmc.set_enabled(val & 0x10 != 0);
+            }
+            0x4017 => {
+                self.five_step = val & 0x80 != 0;
+                self.irq_inhibit = val & 0x40 != 0;
+                if self.irq_inhibit {
+                    self.frame_irq = false;
+                }
+                // The reset waits for the next APU cycle to start.
+                self.frame_reset_delay = if self.cycle % 2 == 1 { 4 } else { 3 };
+            }
+            _ => {}
+        }
+    }
+
+    /// Reads `$4015`, which clears the frame IRQ flag. Bit 5 is not driven.
+    pub fn read_status(&mut self) -> u8 {
+        let val = self.peek_status();
+        self.frame_irq = false;
+        val
+    }
+
+    /// Like [`read_status`](Apu::read_status), without clearing the flag.
+    pub fn peek_status(&self) -> u8 {
+        (self.pulse1.length.active() as u8)
+            | (self.pulse2.length.active() as u8) << 1
+            | (self.triangle.length.active() as u8) << 2
+            | (self.noise.length.active() as u8) << 3
+            | (self.dmc.active() as u8) << 4
+            | (self.frame_irq as u8) << 6
+            | (self.dmc.irq as u8) << 7
+    }
+
+    /// The state of the IRQ output.
+    pub fn irq(&self) -> bool {
+        self.frame_irq || self.dmc.irq
+    }
+
+    /// The number of CPU cycles the APU has run since power on.
+    pub fn cycle(&self) -> u64 {
+        self.cycle
+    }
+
+    /// The address of the next sample byte, once the DMC needs one.
+    pub fn dma_request(&self) -> Option<u16> {
+        self.dmc.dma_request()
+    }
+
+    /// Hands the sample byte the DMA read to the DMC.
+    pub fn dma_complete(&mut self, val: u8) {
+        self.dmc.dma_complete(val);
+    }
+
+    /// The current output level of a channel in the range 0-15, or 0-127
+    /// for the DMC.
+    pub fn output(&self, channel: Channel) -> u8 {
+        match channel {
+            Channel::Pulse1 => self.pulse1.output(),
+            Channel::Pulse2 => self.pulse2.output(),
+            Channel::Triangle => self.triangle.output(),
+            Channel::Noise => self.noise.output(),
+            Channel::Dmc => self.dmc.output(),
+        }
+    }
+}
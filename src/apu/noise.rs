@@ -0,0 +1,96 @@
+use super::envelope::Envelope;
+use super::length_counter::LengthCounter;
+use crate::nes::Region;
+
+/// The timer periods in CPU cycles.
+const NTSC_PERIODS: [u16; 16] = [
+    4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
+];
+const PAL_PERIODS: [u16; 16] = [
+    4, 8, 14, 30, 60, 88, 118, 148, 188, 236, 354, 472, 708, 944, 1890, 3778,
+];
+
+/// The noise channel at `$400C-$400F`, driven by a 15 bit linear feedback
+/// shift register.
+#[derive(Debug)]
+pub struct Noise {
+    periods: &'static [u16; 16],
+    period: u16,
+    timer: u16,
+    /// Takes the feedback from bit 6 instead of bit 1, which gives a short
+    /// sequence of 93 steps.
+    short_mode: bool,
+    shift: u16,
+    pub envelope: Envelope,
+    pub length: LengthCounter,
+}
+
+impl Default for Noise {
+    fn default() -> Self {
+        Self {
+            periods: &NTSC_PERIODS,
+            period: NTSC_PERIODS[0],
+            timer: 0,
+            short_mode: false,
+            shift: 1,
+            envelope: Envelope::default(),
+            length: LengthCounter::default(),
+        }
+    }
+}
+
+impl Noise {
+    pub fn set_region(&mut self, region: Region) {
+        self.periods = match region {
+            Region::Ntsc => &NTSC_PERIODS,
+            Region::Pal => &PAL_PERIODS,
+        };
+    }
+
+    /// Writes one of the four registers, `reg` is the offset from the first one.
+    pub fn write(&mut self, reg: u16, val: u8) {
+        match reg {
+            0 => {
+                self.length.halt = val & 0x20 != 0;
+                self.envelope.write(val);
+            }
+            1 => {}
+            2 => {
+                self.short_mode = val & 0x80 != 0;
+                self.period = self.periods[(val & 0x0F) as usize];
+            }
+            _ => {
+                self.length.load(val);
+                self.envelope.restart();
+            }
+        }
+    }
+
+    /// Clocked on every CPU cycle.
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period - 1;
+            let tap = if self.short_mode { 6 } else { 1 };
+            let feedback = (self.shift ^ (self.shift >> tap)) & 0x01;
+            self.shift = (self.shift >> 1) | (feedback << 14);
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn clock_quarter_frame(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn clock_half_frame(&mut self) {
+        self.length.clock();
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.length.active() || self.shift & 0x01 != 0 {
+            0
+        } else {
+            self.envelope.output()
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use super::envelope::Envelope;
+use super::length_counter::LengthCounter;
+
+/// The four duty cycles, 12.5%, 25%, 50% and 25% negated.
+const DUTY_CYCLES: [[u8; 8]; 4] = [
+    [0, 1, 0, 0, 0, 0, 0, 0],
+    [0, 1, 1, 0, 0, 0, 0, 0],
+    [0, 1, 1, 1, 1, 0, 0, 0],
+    [1, 0, 0, 1, 1, 1, 1, 1],
+];
+
+/// One of the two square wave channels at `$4000-$4003` and `$4004-$4007`.
+#[derive(Debug, Default)]
+pub struct Pulse {
+    /// The sweep of the first channel subtracts the ones' complement of
+    /// the change, which makes it one lower than on the second channel.
+    ones_complement: bool,
+    duty: u8,
+    step: u8,
+    period: u16,
+    timer: u16,
+    pub envelope: Envelope,
+    pub length: LengthCounter,
+
+    sweep_enabled: bool,
+    sweep_period: u8,
+    sweep_negate: bool,
+    sweep_shift: u8,
+    sweep_divider: u8,
+    sweep_reload: bool,
+}
+
+impl Pulse {
+    pub fn new(ones_complement: bool) -> Self {
+        Self {
+            ones_complement,
+            ..Default::default()
+        }
+    }
+
+    /// Writes one of the four registers, `reg` is the offset from the first one.
+    pub fn write(&mut self, reg: u16, val: u8) {
+        match reg {
+            0 => {
+                self.duty = val >> 6;
+                self.length.halt = val & 0x20 != 0;
+                self.envelope.write(val);
+            }
+            1 => {
+                self.sweep_enabled = val & 0x80 != 0;
+                self.sweep_period = (val >> 4) & 0x07;
+                self.sweep_negate = val & 0x08 != 0;
+                self.sweep_shift = val & 0x07;
+                self.sweep_reload = true;
+            }
+            2 => self.period = (self.period & 0x0700) | val as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((val as u16 & 0x07) << 8);
+                self.length.load(val);
+                self.envelope.restart();
+                self.step = 0;
+            }
+        }
+    }
+
+    /// Clocked on every APU cycle, which is every other CPU cycle.
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            self.step = (self.step + 1) % 8;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn clock_quarter_frame(&mut self) {
+        self.envelope.clock();
+    }
+
+    pub fn clock_half_frame(&mut self) {
+        self.length.clock();
+
+        if self.sweep_divider == 0 && self.sweep_enabled && self.sweep_shift > 0 && !self.muted() {
+            self.period = self.sweep_target();
+        }
+        if self.sweep_divider == 0 || self.sweep_reload {
+            self.sweep_divider = self.sweep_period;
+            self.sweep_reload = false;
+        } else {
+            self.sweep_divider -= 1;
+        }
+    }
+
+    /// The period the sweep unit is heading to. It's computed all the time,
+    /// even while the sweep is disabled.
+    fn sweep_target(&self) -> u16 {
+        let change = self.period >> self.sweep_shift;
+        if !self.sweep_negate {
+            self.period + change
+        } else if self.ones_complement {
+            self.period.saturating_sub(change + 1)
+        } else {
+            self.period.saturating_sub(change)
+        }
+    }
+
+    fn muted(&self) -> bool {
+        self.period < 8 || self.sweep_target() > 0x7FF
+    }
+
+    pub fn output(&self) -> u8 {
+        if !self.length.active()
+            || self.muted()
+            || DUTY_CYCLES[self.duty as usize][self.step as usize] == 0
+        {
+            0
+        } else {
+            self.envelope.output()
+        }
+    }
+}
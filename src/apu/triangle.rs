@@ -0,0 +1,74 @@
+use super::length_counter::LengthCounter;
+
+/// The 32 steps of the triangle wave.
+const SEQUENCE: [u8; 32] = [
+    15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12,
+    13, 14, 15,
+];
+
+/// The triangle channel at `$4008-$400B`.
+#[derive(Debug, Default)]
+pub struct Triangle {
+    step: u8,
+    period: u16,
+    timer: u16,
+    pub length: LengthCounter,
+
+    /// Doubles as the halt flag of the length counter.
+    control: bool,
+    linear_reload_value: u8,
+    linear_counter: u8,
+    linear_reload: bool,
+}
+
+impl Triangle {
+    /// Writes one of the four registers, `reg` is the offset from the first one.
+    pub fn write(&mut self, reg: u16, val: u8) {
+        match reg {
+            0 => {
+                self.control = val & 0x80 != 0;
+                self.length.halt = self.control;
+                self.linear_reload_value = val & 0x7F;
+            }
+            1 => {}
+            2 => self.period = (self.period & 0x0700) | val as u16,
+            _ => {
+                self.period = (self.period & 0x00FF) | ((val as u16 & 0x07) << 8);
+                self.length.load(val);
+                self.linear_reload = true;
+            }
+        }
+    }
+
+    /// Clocked on every CPU cycle. The sequence stops whenever one of the
+    /// counters runs out, so the output holds its last value.
+    pub fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            if self.length.active() && self.linear_counter > 0 {
+                self.step = (self.step + 1) % 32;
+            }
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    pub fn clock_quarter_frame(&mut self) {
+        if self.linear_reload {
+            self.linear_counter = self.linear_reload_value;
+        } else if self.linear_counter > 0 {
+            self.linear_counter -= 1;
+        }
+        if !self.control {
+            self.linear_reload = false;
+        }
+    }
+
+    pub fn clock_half_frame(&mut self) {
+        self.length.clock();
+    }
+
+    pub fn output(&self) -> u8 {
+        SEQUENCE[self.step as usize]
+    }
+}
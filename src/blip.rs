@@ -0,0 +1,98 @@
+//! Band-limited step synthesis, which turns a signal that changes at the CPU
+//! clock rate into samples at the rate of the host without aliasing.
+
+use std::f64::consts::PI;
+
+/// The number of output samples a single step is spread over.
+const KERNEL_WIDTH: usize = 16;
+/// The number of sub-sample positions the kernel is computed for.
+const PHASES: usize = 32;
+/// The cutoff of the kernel, as a fraction of the output sample rate.
+const CUTOFF: f64 = 0.45;
+
+/// A buffer of steps in the input signal, which are read back as samples.
+#[derive(Debug)]
+pub struct BlipBuffer {
+    /// The steps of every phase, each of them adds up to 1.
+    kernel: Vec<[f32; KERNEL_WIDTH]>,
+    /// The number of output samples per input clock.
+    factor: f64,
+    /// The time of the next clock, in output samples from the start of `deltas`.
+    time: f64,
+    /// The difference of every sample to the one before.
+    deltas: Vec<f32>,
+    /// The sum of all deltas that were read.
+    integrator: f32,
+}
+
+impl BlipBuffer {
+    pub fn new(clock_rate: f64, sample_rate: f64) -> Self {
+        Self {
+            kernel: (0..PHASES).map(kernel).collect(),
+            factor: sample_rate / clock_rate,
+            time: 0.0,
+            deltas: vec![0.0; KERNEL_WIDTH],
+            integrator: 0.0,
+        }
+    }
+
+    /// Adds a step of `delta` to the signal at the current time.
+    pub fn add_delta(&mut self, delta: f32) {
+        let start = self.time as usize;
+        let phase = ((self.time - start as f64) * PHASES as f64) as usize;
+        for (d, k) in self.deltas[start..].iter_mut().zip(&self.kernel[phase]) {
+            *d += delta * k;
+        }
+    }
+
+    /// Advances the time by a single input clock.
+    pub fn clock(&mut self) {
+        self.time += self.factor;
+        let needed = self.time as usize + KERNEL_WIDTH;
+        if self.deltas.len() < needed {
+            self.deltas.resize(needed, 0.0);
+        }
+    }
+
+    /// The number of samples that won't change anymore.
+    pub fn available(&self) -> usize {
+        self.time as usize
+    }
+
+    /// Removes up to `count` finished samples and passes them to `f`.
+    pub fn read(&mut self, count: usize, mut f: impl FnMut(f32)) -> usize {
+        let count = count.min(self.available());
+        for d in self.deltas.drain(..count) {
+            self.integrator += d;
+            f(self.integrator);
+        }
+        self.time -= count as f64;
+        count
+    }
+}
+
+/// A windowed sinc impulse, delayed by half the kernel width plus the
+/// fraction of a sample that belongs to `phase`.
+fn kernel(phase: usize) -> [f32; KERNEL_WIDTH] {
+    let center = (KERNEL_WIDTH / 2) as f64;
+    let offset = phase as f64 / PHASES as f64;
+
+    let mut taps = [0.0; KERNEL_WIDTH];
+    for (k, tap) in taps.iter_mut().enumerate() {
+        let x = k as f64 - center - offset;
+        let sinc = if x == 0.0 {
+            1.0
+        } else {
+            (2.0 * PI * CUTOFF * x).sin() / (2.0 * PI * CUTOFF * x)
+        };
+        let window = 0.42 + 0.5 * (PI * x / center).cos() + 0.08 * (2.0 * PI * x / center).cos();
+        *tap = sinc * window.max(0.0);
+    }
+
+    let sum: f64 = taps.iter().sum();
+    let mut kernel = [0.0; KERNEL_WIDTH];
+    for (k, tap) in kernel.iter_mut().zip(&taps) {
+        *k = (tap / sum) as f32;
+    }
+    kernel
+}
@@ -1,30 +1,110 @@
+use crate::apu::Apu;
 use crate::cartridge::Cartridge;
-use crate::mem::{Memory, Ram};
+use crate::controller::Controller;
+use crate::mem::{DmaBus, Memory, Ram};
+use crate::ppu::Ppu;
 
 #[derive(Default)]
 pub struct Bus {
     ram: Ram,
-    cartridge: Cartridge,
+    pub ppu: Ppu,
+    pub apu: Apu,
+    pub cartridge: Cartridge,
+    pub controllers: [Controller; 2],
+    /// The last value on the CPU data bus, which is what reads from
+    /// addresses that nothing drives return.
+    open_bus: u8,
+}
+
+impl Bus {
+    pub fn new(cartridge: Cartridge) -> Self {
+        Self {
+            cartridge,
+            ..Default::default()
+        }
+    }
+
+    /// Advances the PPU by a single dot.
+    pub fn clock_ppu(&mut self) {
+        self.ppu.clock(&mut self.cartridge);
+    }
+
+    /// Advances the cartridge by a single CPU cycle.
+    pub fn clock_cartridge(&mut self) {
+        self.cartridge.clock();
+    }
+
+    /// Advances the APU by a single CPU cycle.
+    pub fn clock_apu(&mut self) {
+        self.apu.clock();
+    }
+
+    /// The state of the IRQ line, which is pulled low by any of the connected devices.
+    pub fn irq(&self) -> bool {
+        self.cartridge.irq() || self.apu.irq()
+    }
+
+    pub fn open_bus(&self) -> u8 {
+        self.open_bus
+    }
 }
 
 impl Memory for Bus {
-    fn read(&self, addr: u16) -> u8 {
-        match addr {
+    fn read(&mut self, addr: u16) -> u8 {
+        let val = match addr {
+            // The status is read inside the CPU, so it doesn't show up on
+            // the external data bus.
+            0x4015 => return self.apu.read_status() | (self.open_bus & 0x20),
             0x0000..=0x1FFF => self.ram.read(addr),
-            0x2000..=0x3FFF => todo!("read from ppu"),
-            0x4000..=0x4017 => todo!("read from apu or io registers"),
-            0x4018..=0x401F => panic!("this memory region is disabled"),
-            0x4020..=0xFFFF => todo!("read from cartridge"),
+            0x2000..=0x3FFF => self.ppu.read_register(addr, &mut self.cartridge),
+            // The controllers only drive the lowest bits.
+            0x4016 => self.controllers[0].read() | (self.open_bus & 0xE0),
+            0x4017 => self.controllers[1].read() | (self.open_bus & 0xE0),
+            // The other APU registers are write only, and $4018-$401F is disabled.
+            0x4000..=0x401F => self.open_bus,
+            0x4020..=0xFFFF => self.cartridge.read_prg(addr).unwrap_or(self.open_bus),
+        };
+        self.open_bus = val;
+        val
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        match addr {
+            0x0000..=0x1FFF => self.ram.peek(addr),
+            0x2000..=0x3FFF => self.ppu.peek_register(addr),
+            0x4015 => self.apu.peek_status() | (self.open_bus & 0x20),
+            0x4016 => self.controllers[0].peek() | (self.open_bus & 0xE0),
+            0x4017 => self.controllers[1].peek() | (self.open_bus & 0xE0),
+            0x4000..=0x401F => self.open_bus,
+            0x4020..=0xFFFF => self.cartridge.read_prg(addr).unwrap_or(self.open_bus),
         }
     }
 
     fn write(&mut self, addr: u16, val: u8) {
+        self.open_bus = val;
         match addr {
             0x0000..=0x1FFF => self.ram.write(addr, val),
-            0x2000..=0x3FFF => todo!("write from ppu"),
-            0x4000..=0x4017 => todo!("write from apu or io registers"),
-            0x4018..=0x401F => panic!("this memory region is disabled"),
-            0x4020..=0xFFFF => todo!("write from cartridge"),
+            0x2000..=0x3FFF => self.ppu.write_register(addr, val, &mut self.cartridge),
+            // Both controllers share the strobe line.
+            0x4016 => self.controllers.iter_mut().for_each(|c| c.write(val)),
+            0x4000..=0x4013 | 0x4015 | 0x4017 => self.apu.write_register(addr, val),
+            // OAM DMA is not emulated yet, and $4018-$401F is disabled.
+            0x4014 | 0x4018..=0x401F => {}
+            0x4020..=0xFFFF => self.cartridge.write_prg(addr, val),
         };
     }
 }
+
+impl DmaBus for Bus {
+    fn dma_request(&self) -> Option<u16> {
+        self.apu.dma_request()
+    }
+
+    fn dma_complete(&mut self, val: u8) {
+        self.apu.dma_complete(val);
+    }
+
+    fn apu_cycle(&self) -> u64 {
+        self.apu.cycle()
+    }
+}
@@ -1,13 +1,35 @@
-use std::io::{self, prelude::*};
+use crate::mapper::{self, Mapped, Mapper};
+use std::{
+    fs::{self, File},
+    io::{self, prelude::*, BufReader},
+    ops::Range,
+    path::{Path, PathBuf},
+};
 use thiserror::Error;
 
+const TRAINER_SIZE: usize = 0x200;
+const TRAINER_ADDR: u16 = 0x7000;
+
 #[derive(Error, Debug)]
 pub enum CartridgeLoadError {
     #[error("failed to read input")]
     IoError(#[from] io::Error),
-    // TODO: Better and nicer errors
-    #[error("rom has invalid format")]
-    FormatError,
+    #[error("input ended inside the 16 byte header")]
+    TruncatedHeader,
+    #[error("header does not start with `NES<EOF>`")]
+    BadMagic,
+    #[error("input ended inside the 512 byte trainer")]
+    TruncatedTrainer,
+    #[error("header declares a PRG-ROM size that is zero or out of range")]
+    InvalidSize,
+    #[error("expected {expected} bytes of PRG-ROM, but got {got}")]
+    TruncatedPrg { expected: usize, got: usize },
+    #[error("expected {expected} bytes of CHR-ROM, but got {got}")]
+    TruncatedChr { expected: usize, got: usize },
+    #[error("found unexpected data after the CHR-ROM")]
+    TrailingData,
+    #[error("mapper {0} is not supported")]
+    UnsupportedMapper(u16),
 }
 
 #[derive(Debug)]
@@ -15,50 +37,663 @@ pub struct Cartridge {
     pub header: CartridgeHeader,
     pub prg_rom: Vec<u8>,
     pub chr_rom: Vec<u8>,
+    pub prg_ram: Vec<u8>,
+    /// Boards without CHR-ROM have CHR-RAM instead.
+    pub chr_ram: Vec<u8>,
+    /// The 512 byte trainer, which is empty if the image has none.
+    pub trainer: Vec<u8>,
+    /// The file that battery-backed PRG-RAM is persisted to.
+    save: Option<SaveFile>,
+    /// The nametable RAM for the third and fourth nametable on four-screen boards.
+    pub vram: Vec<u8>,
+    mapper: Box<dyn Mapper>,
+}
+
+impl Default for Cartridge {
+    /// Creates an empty cartridge slot.
+    fn default() -> Self {
+        Self {
+            header: CartridgeHeader::default(),
+            prg_rom: Vec::new(),
+            chr_rom: Vec::new(),
+            prg_ram: Vec::new(),
+            chr_ram: Vec::new(),
+            trainer: Vec::new(),
+            save: None,
+            vram: Vec::new(),
+            mapper: Box::new(mapper::Empty),
+        }
+    }
 }
 
 impl Cartridge {
     pub fn load(r: &mut dyn Read) -> Result<Cartridge, CartridgeLoadError> {
-        // TODO: Replace this with nom and much better error handling
         let mut header = [0u8; 16];
+        if read_up_to(r, &mut header)? < header.len() {
+            return Err(CartridgeLoadError::TruncatedHeader);
+        }
 
         if header[0..4] != *b"NES\x1a" {
-            return Err(CartridgeLoadError::FormatError);
+            return Err(CartridgeLoadError::BadMagic);
         }
 
-        let header = CartridgeHeader {
-            prg_rom_chunks: header[4],
-            chr_rom_chunks: header[5],
-            flags_6: header[6],
-            flags_7: header[7],
-            prg_ram_size: header[8],
-            flags_9: header[9],
-            flags_10: header[10],
+        let header = CartridgeHeader::from_bytes(&header);
+
+        let trainer = if header.trainer() {
+            let trainer = read_chunk(r, TRAINER_SIZE)?;
+            if trainer.len() < TRAINER_SIZE {
+                return Err(CartridgeLoadError::TruncatedTrainer);
+            }
+            trainer
+        } else {
+            Vec::new()
         };
 
-        let prg_bytes = header.prg_rom_chunks * 16384;
-        let mut prg_rom = vec![0u8; prg_bytes as usize];
-        r.read(&mut prg_rom)?;
+        let expected = header.prg_rom_bytes();
+        if expected == 0 {
+            return Err(CartridgeLoadError::InvalidSize);
+        }
+        let prg_rom = read_chunk(r, expected)?;
+        if prg_rom.len() < expected {
+            return Err(CartridgeLoadError::TruncatedPrg {
+                expected,
+                got: prg_rom.len(),
+            });
+        }
+
+        let expected = header.chr_rom_bytes();
+        let chr_rom = read_chunk(r, expected)?;
+        if chr_rom.len() < expected {
+            return Err(CartridgeLoadError::TruncatedChr {
+                expected,
+                got: chr_rom.len(),
+            });
+        }
+
+        // NES 2.0 images may carry miscellaneous ROMs behind the CHR-ROM.
+        // iNES dumps often have junk or a title appended, which is ignored.
+        if header.format() == HeaderFormat::Nes2
+            && header.flags_14 & 0x03 == 0
+            && read_up_to(r, &mut [0u8])? != 0
+        {
+            return Err(CartridgeLoadError::TrailingData);
+        }
 
-        let chr_bytes = header.chr_rom_chunks * 8192;
-        let mut chr_rom = vec![0u8; chr_bytes as usize];
-        r.read(&mut chr_rom)?;
+        let mapper = mapper::from_header(&header)
+            .ok_or_else(|| CartridgeLoadError::UnsupportedMapper(header.mapper()))?;
+
+        // The trainer needs somewhere to live, even if the header declares no PRG-RAM.
+        // Boards without PRG-RAM of their own get these 8 KiB at $6000-$7FFF,
+        // see `trainer_ram`.
+        let mut prg_ram_bytes = header.prg_ram_bytes() + header.prg_nvram_bytes();
+        if !trainer.is_empty() {
+            prg_ram_bytes = prg_ram_bytes.max(0x2000);
+        }
+        let chr_ram = vec![0u8; header.chr_ram_bytes() + header.chr_nvram_bytes()];
+        let vram = if header.four_screen() {
+            vec![0u8; 0x800]
+        } else {
+            Vec::new()
+        };
 
-        Ok(Cartridge {
+        let mut cart = Cartridge {
             header,
             prg_rom,
             chr_rom,
-        })
+            prg_ram: vec![0u8; prg_ram_bytes],
+            chr_ram,
+            trainer,
+            save: None,
+            vram,
+            mapper,
+        };
+        cart.load_trainer();
+        Ok(cart)
+    }
+
+    /// Loads a ROM image from a file. If the cartridge has a battery, the
+    /// save file next to it with the `.sav` extension is attached, see
+    /// [`attach_save_file`](Cartridge::attach_save_file).
+    pub fn open(path: impl AsRef<Path>) -> Result<Cartridge, CartridgeLoadError> {
+        let path = path.as_ref();
+        let mut cart = Cartridge::load(&mut BufReader::new(File::open(path)?))?;
+        if cart.has_battery() {
+            cart.attach_save_file(path.with_extension("sav"))?;
+        }
+        Ok(cart)
+    }
+
+    pub fn has_battery(&self) -> bool {
+        self.header.battery()
+    }
+
+    /// The part of `prg_ram` that is backed by the battery. It follows the
+    /// volatile PRG-RAM, like the second bank on SOROM boards.
+    fn nvram_range(&self) -> Range<usize> {
+        if !self.has_battery() {
+            return 0..0;
+        }
+        let start = self.header.prg_ram_bytes().min(self.prg_ram.len());
+        let end = (start + self.header.prg_nvram_bytes()).min(self.prg_ram.len());
+        start..end
+    }
+
+    /// The contents of the battery-backed PRG-RAM at `$6000-$7FFF`, which
+    /// is empty if the cartridge has no battery.
+    pub fn nvram(&self) -> &[u8] {
+        &self.prg_ram[self.nvram_range()]
+    }
+
+    /// Restores the battery-backed PRG-RAM from a save. Saves of a different
+    /// size are truncated or padded with zeroes.
+    pub fn set_nvram(&mut self, data: &[u8]) {
+        let range = self.nvram_range();
+        let nvram = &mut self.prg_ram[range];
+        let len = data.len().min(nvram.len());
+        nvram[..len].copy_from_slice(&data[..len]);
+        for b in &mut nvram[len..] {
+            *b = 0;
+        }
+    }
+
+    /// Persists the battery-backed PRG-RAM to `path`. An existing save is
+    /// loaded immediately, and the RAM is written back on every
+    /// [`flush_save`](Cartridge::flush_save) and when the cartridge is dropped.
+    pub fn attach_save_file(&mut self, path: impl Into<PathBuf>) -> io::Result<()> {
+        let path = path.into();
+        match fs::read(&path) {
+            Ok(data) => self.set_nvram(&data),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        self.save = Some(SaveFile { path, dirty: false });
+        Ok(())
+    }
+
+    /// Writes the battery-backed PRG-RAM to the attached save file,
+    /// if it has changed since the last flush.
+    pub fn flush_save(&mut self) -> io::Result<()> {
+        let range = self.nvram_range();
+        if let Some(save) = &mut self.save {
+            if save.dirty {
+                fs::write(&save.path, &self.prg_ram[range])?;
+                save.dirty = false;
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies the trainer into the PRG-RAM that the mapper shows at
+    /// `$7000-$71FF` after power on.
+    fn load_trainer(&mut self) {
+        for (addr, &val) in (TRAINER_ADDR..).zip(&self.trainer) {
+            if let Mapped::PrgRam(idx) = self.trainer_ram(addr, self.mapper.map_prg_read(addr)) {
+                if let Some(b) = self.prg_ram.get_mut(idx) {
+                    *b = val;
+                }
+            }
+        }
+    }
+
+    /// Falls back to the PRG-RAM at `$6000-$7FFF` for images with a trainer,
+    /// where the board itself has nothing there.
+    fn trainer_ram(&self, addr: u16, mapped: Mapped) -> Mapped {
+        match mapped {
+            Mapped::None if !self.trainer.is_empty() && (0x6000..=0x7FFF).contains(&addr) => {
+                Mapped::PrgRam(addr as usize & 0x1FFF)
+            }
+            _ => mapped,
+        }
+    }
+
+    /// The current nametable arrangement, which is either hardwired on the
+    /// board or controlled by the mapper.
+    pub fn mirroring(&self) -> Mirroring {
+        self.mapper.mirroring()
+    }
+
+    /// The state of the IRQ output of the mapper.
+    pub fn irq(&self) -> bool {
+        self.mapper.irq()
+    }
+
+    /// Advances the mapper by a single CPU cycle.
+    pub fn clock(&mut self) {
+        self.mapper.clock();
+    }
+
+    /// Notifies the mapper about an address on the PPU address bus, that is
+    /// not caused by a memory access.
+    pub fn ppu_address(&mut self, addr: u16) {
+        self.mapper.ppu_address(addr);
+    }
+
+    /// Reads from `$4020-$FFFF` in the CPU address space. Returns `None` if nothing
+    /// on the cartridge drives the data bus.
+    pub fn read_prg(&self, addr: u16) -> Option<u8> {
+        match self.trainer_ram(addr, self.mapper.map_prg_read(addr)) {
+            Mapped::PrgRom(idx) => self.prg_rom.get(idx).copied(),
+            Mapped::PrgRam(idx) => self.prg_ram.get(idx).copied(),
+            _ => None,
+        }
+    }
+
+    /// Writes into `$4020-$FFFF` in the CPU address space.
+    pub fn write_prg(&mut self, addr: u16, val: u8) {
+        let val = match self.mapper.map_prg_read(addr) {
+            Mapped::PrgRom(idx) if self.mapper.bus_conflicts() => {
+                val & self.prg_rom.get(idx).copied().unwrap_or(0xFF)
+            }
+            _ => val,
+        };
+
+        let mapped = self.mapper.map_prg_write(addr, val);
+        if let Mapped::PrgRam(idx) = self.trainer_ram(addr, mapped) {
+            let battery = self.nvram_range().contains(&idx);
+            if let Some(b) = self.prg_ram.get_mut(idx) {
+                *b = val;
+                match &mut self.save {
+                    Some(save) if battery => save.dirty = true,
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Reads from `$0000-$3EFF` in the PPU address space, where `vram` is the
+    /// nametable RAM inside the console.
+    pub fn read_ppu(&mut self, addr: u16, vram: &[u8]) -> u8 {
+        self.mapper.ppu_address(addr);
+        let mapped = if addr < 0x2000 {
+            self.mapper.map_chr(addr)
+        } else {
+            self.mapper.map_nametable(addr)
+        };
+
+        match mapped {
+            Mapped::Chr(idx) => self.chr().get(idx).copied().unwrap_or(0),
+            Mapped::Vram(idx) if idx < 0x800 => vram[idx],
+            Mapped::Vram(idx) => self.vram.get(idx - 0x800).copied().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    /// Writes into `$0000-$3EFF` in the PPU address space.
+    /// Writes to CHR-ROM are ignored.
+    pub fn write_ppu(&mut self, addr: u16, val: u8, vram: &mut [u8]) {
+        self.mapper.ppu_address(addr);
+        let mapped = if addr < 0x2000 {
+            self.mapper.map_chr(addr)
+        } else {
+            self.mapper.map_nametable(addr)
+        };
+
+        match mapped {
+            Mapped::Chr(idx) => {
+                if let Some(b) = self.chr_ram.get_mut(idx) {
+                    *b = val;
+                }
+            }
+            Mapped::Vram(idx) if idx < 0x800 => vram[idx] = val,
+            Mapped::Vram(idx) => {
+                if let Some(b) = self.vram.get_mut(idx - 0x800) {
+                    *b = val;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The pattern table memory, which is either CHR-ROM or CHR-RAM.
+    fn chr(&self) -> &[u8] {
+        if self.chr_ram.is_empty() {
+            &self.chr_rom
+        } else {
+            &self.chr_ram
+        }
+    }
+}
+
+impl Drop for Cartridge {
+    fn drop(&mut self) {
+        // There is no way to report the error here, callers that care
+        // should flush before dropping the cartridge.
+        let _ = self.flush_save();
     }
 }
 
 #[derive(Debug)]
+struct SaveFile {
+    path: PathBuf,
+    /// Whether the PRG-RAM was written since the last flush.
+    dirty: bool,
+}
+
+/// Reads until `buf` is full or the input ends, and returns the number of bytes read.
+fn read_up_to(r: &mut dyn Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match r.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+/// Reads at most `len` bytes. The buffer grows with the input, so a bogus
+/// size in the header can't allocate more memory than the image has.
+fn read_chunk(r: &mut dyn Read, len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    r.take(len as u64).read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+/// The arrangement of the two physical nametables inside the
+/// four logical nametables at `$2000-$2FFF`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirroring {
+    Horizontal,
+    Vertical,
+    /// All four nametables show the first physical nametable.
+    SingleScreenLower,
+    /// All four nametables show the second physical nametable.
+    SingleScreenUpper,
+    /// The board has 2 KiB of extra VRAM for the third and fourth nametable.
+    FourScreen,
+}
+
+impl Mirroring {
+    /// Maps an address in the nametable region to an index into the 2 KiB VRAM.
+    /// With four-screen mirroring, indices from `$800` on refer to the VRAM
+    /// on the cartridge.
+    pub fn nametable_index(self, addr: u16) -> usize {
+        let addr = addr as usize & 0x0FFF;
+        let (table, offset) = (addr / 0x400, addr & 0x3FF);
+        let table = match self {
+            Mirroring::Horizontal => table >> 1,
+            Mirroring::Vertical => table & 1,
+            Mirroring::SingleScreenLower => 0,
+            Mirroring::SingleScreenUpper => 1,
+            Mirroring::FourScreen => table,
+        };
+        table * 0x400 + offset
+    }
+}
+
+/// The layout of an iNES header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderFormat {
+    /// An iNES 1.0 header.
+    Ines,
+    /// An iNES 1.0 header with garbage in bytes 7-15, like the "DiskDude!"
+    /// signature some old dumping tools left behind. Only `flags_6` is
+    /// trusted for those.
+    ArchaicInes,
+    Nes2,
+}
+
+/// The type of console the cartridge was made for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleType {
+    Nes,
+    VsSystem,
+    Playchoice10,
+    /// One of the extended console types from byte 13 of a NES 2.0 header.
+    Extended(u8),
+}
+
+/// The CPU/PPU timing the cartridge expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timing {
+    Ntsc,
+    Pal,
+    /// The game runs on both NTSC and PAL consoles.
+    MultiRegion,
+    Dendy,
+}
+
+/// The input device the game expects to be plugged in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpansionDevice {
+    Unspecified,
+    StandardControllers,
+    FourScore,
+    FamicomFourPlayers,
+    VsSystem,
+    Zapper,
+    /// Any other device number from byte 15 of a NES 2.0 header.
+    Other(u8),
+}
+
+#[derive(Debug, Default)]
 pub struct CartridgeHeader {
     pub prg_rom_chunks: u8,
     pub chr_rom_chunks: u8,
     pub flags_6: u8,
     pub flags_7: u8,
-    pub prg_ram_size: u8,
+    pub flags_8: u8,
     pub flags_9: u8,
     pub flags_10: u8,
+    pub flags_11: u8,
+    pub flags_12: u8,
+    pub flags_13: u8,
+    pub flags_14: u8,
+    pub flags_15: u8,
+}
+
+impl CartridgeHeader {
+    /// Parses the header from the first 16 bytes of a ROM image.
+    /// The magic number is not checked.
+    pub fn from_bytes(raw: &[u8; 16]) -> Self {
+        Self {
+            prg_rom_chunks: raw[4],
+            chr_rom_chunks: raw[5],
+            flags_6: raw[6],
+            flags_7: raw[7],
+            flags_8: raw[8],
+            flags_9: raw[9],
+            flags_10: raw[10],
+            flags_11: raw[11],
+            flags_12: raw[12],
+            flags_13: raw[13],
+            flags_14: raw[14],
+            flags_15: raw[15],
+        }
+    }
+
+    pub fn format(&self) -> HeaderFormat {
+        if self.flags_7 & 0x0C == 0x08 {
+            HeaderFormat::Nes2
+        } else if self.flags_12 | self.flags_13 | self.flags_14 | self.flags_15 != 0 {
+            HeaderFormat::ArchaicInes
+        } else {
+            HeaderFormat::Ines
+        }
+    }
+
+    fn is_nes2(&self) -> bool {
+        self.format() == HeaderFormat::Nes2
+    }
+
+    /// The number of the mapper. NES 2.0 headers extend it to 12 bits
+    /// using the lower nibble of `flags_8`.
+    pub fn mapper(&self) -> u16 {
+        let lower = (self.flags_6 >> 4) as u16;
+        match self.format() {
+            HeaderFormat::Nes2 => {
+                ((self.flags_8 as u16 & 0x0F) << 8) | (self.flags_7 as u16 & 0xF0) | lower
+            }
+            HeaderFormat::Ines => (self.flags_7 as u16 & 0xF0) | lower,
+            HeaderFormat::ArchaicInes => lower,
+        }
+    }
+
+    /// The submapper number, which is always zero for iNES 1.0.
+    pub fn submapper(&self) -> u8 {
+        if self.is_nes2() {
+            self.flags_8 >> 4
+        } else {
+            0
+        }
+    }
+
+    /// The nametable mirroring, that is hardwired on the board.
+    pub fn mirroring(&self) -> Mirroring {
+        if self.four_screen() {
+            Mirroring::FourScreen
+        } else if self.flags_6 & 0x01 != 0 {
+            Mirroring::Vertical
+        } else {
+            Mirroring::Horizontal
+        }
+    }
+
+    /// Whether the cartridge contains battery-backed memory.
+    pub fn battery(&self) -> bool {
+        self.flags_6 & 0x02 != 0
+    }
+
+    /// Whether a 512 byte trainer sits between the header and the PRG-ROM.
+    pub fn trainer(&self) -> bool {
+        self.flags_6 & 0x04 != 0
+    }
+
+    /// Whether the board provides its own VRAM for all four nametables.
+    pub fn four_screen(&self) -> bool {
+        self.flags_6 & 0x08 != 0
+    }
+
+    pub fn console_type(&self) -> ConsoleType {
+        if self.format() == HeaderFormat::ArchaicInes {
+            return ConsoleType::Nes;
+        }
+
+        match self.flags_7 & 0x03 {
+            0 => ConsoleType::Nes,
+            1 => ConsoleType::VsSystem,
+            2 => ConsoleType::Playchoice10,
+            _ if self.is_nes2() => ConsoleType::Extended(self.flags_13 & 0x0F),
+            _ => ConsoleType::Nes,
+        }
+    }
+
+    pub fn timing(&self) -> Timing {
+        match self.format() {
+            HeaderFormat::Nes2 => match self.flags_12 & 0x03 {
+                0 => Timing::Ntsc,
+                1 => Timing::Pal,
+                2 => Timing::MultiRegion,
+                _ => Timing::Dendy,
+            },
+            HeaderFormat::Ines if self.flags_9 & 0x01 != 0 => Timing::Pal,
+            _ => Timing::Ntsc,
+        }
+    }
+
+    /// The size of the PRG-ROM in bytes.
+    pub fn prg_rom_bytes(&self) -> usize {
+        if self.is_nes2() {
+            rom_bytes(self.prg_rom_chunks, self.flags_9 & 0x0F, 0x4000)
+        } else {
+            self.prg_rom_chunks as usize * 0x4000
+        }
+    }
+
+    /// The size of the CHR-ROM in bytes.
+    pub fn chr_rom_bytes(&self) -> usize {
+        if self.is_nes2() {
+            rom_bytes(self.chr_rom_chunks, self.flags_9 >> 4, 0x2000)
+        } else {
+            self.chr_rom_chunks as usize * 0x2000
+        }
+    }
+
+    /// The size of the volatile PRG-RAM in bytes. iNES 1.0 headers treat a
+    /// size of zero as 8 KiB for compatibility with older dumps, and all of
+    /// it is battery-backed if the battery flag is set.
+    pub fn prg_ram_bytes(&self) -> usize {
+        match self.format() {
+            HeaderFormat::Nes2 => shift_bytes(self.flags_10 & 0x0F),
+            _ if self.battery() => 0,
+            HeaderFormat::Ines => self.flags_8.max(1) as usize * 0x2000,
+            HeaderFormat::ArchaicInes => 0x2000,
+        }
+    }
+
+    /// The size of the battery-backed PRG-RAM in bytes.
+    pub fn prg_nvram_bytes(&self) -> usize {
+        match self.format() {
+            HeaderFormat::Nes2 => shift_bytes(self.flags_10 >> 4),
+            HeaderFormat::Ines if self.battery() => self.flags_8.max(1) as usize * 0x2000,
+            HeaderFormat::ArchaicInes if self.battery() => 0x2000,
+            _ => 0,
+        }
+    }
+
+    /// The size of the volatile CHR-RAM in bytes. iNES 1.0 headers imply
+    /// 8 KiB if there is no CHR-ROM.
+    pub fn chr_ram_bytes(&self) -> usize {
+        if self.is_nes2() {
+            shift_bytes(self.flags_11 & 0x0F)
+        } else if self.chr_rom_chunks == 0 {
+            0x2000
+        } else {
+            0
+        }
+    }
+
+    /// The size of the battery-backed CHR-RAM in bytes.
+    pub fn chr_nvram_bytes(&self) -> usize {
+        if self.is_nes2() {
+            shift_bytes(self.flags_11 >> 4)
+        } else {
+            0
+        }
+    }
+
+    pub fn expansion_device(&self) -> ExpansionDevice {
+        if !self.is_nes2() {
+            return ExpansionDevice::Unspecified;
+        }
+
+        match self.flags_15 & 0x3F {
+            0x00 => ExpansionDevice::Unspecified,
+            0x01 => ExpansionDevice::StandardControllers,
+            0x02 => ExpansionDevice::FourScore,
+            0x03 => ExpansionDevice::FamicomFourPlayers,
+            0x04 => ExpansionDevice::VsSystem,
+            0x08 => ExpansionDevice::Zapper,
+            dev => ExpansionDevice::Other(dev),
+        }
+    }
+}
+
+/// Decodes a NES 2.0 ROM size. If the upper nibble is `$F`, the lower byte
+/// holds the size as `2^E * (M * 2 + 1)`, otherwise the size is counted in
+/// `unit` bytes.
+fn rom_bytes(lsb: u8, msb: u8, unit: usize) -> usize {
+    if msb == 0x0F {
+        let exponent = (lsb >> 2) as u32;
+        let multiplier = (lsb & 0x03) as usize * 2 + 1;
+        1usize
+            .checked_shl(exponent)
+            .and_then(|size| size.checked_mul(multiplier))
+            .unwrap_or(0)
+    } else {
+        ((msb as usize) << 8 | lsb as usize) * unit
+    }
+}
+
+/// Decodes a NES 2.0 RAM size, which is `64 << shift` bytes or nothing
+/// if the shift is zero.
+fn shift_bytes(shift: u8) -> usize {
+    if shift == 0 {
+        0
+    } else {
+        64 << shift
+    }
 }
@@ -0,0 +1,62 @@
+/// The buttons of a standard controller, in the order they are shifted out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Button {
+    A = 1 << 0,
+    B = 1 << 1,
+    Select = 1 << 2,
+    Start = 1 << 3,
+    Up = 1 << 4,
+    Down = 1 << 5,
+    Left = 1 << 6,
+    Right = 1 << 7,
+}
+
+/// A standard controller, which is read one button at a time through a
+/// shift register.
+#[derive(Debug, Default)]
+pub struct Controller {
+    /// The buttons that are currently held, one bit per [`Button`].
+    pub buttons: u8,
+    shift: u8,
+    strobe: bool,
+}
+
+impl Controller {
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        if pressed {
+            self.buttons |= button as u8;
+        } else {
+            self.buttons &= !(button as u8);
+        }
+    }
+
+    /// Handles a write to `$4016`. While bit 0 is set, the shift register
+    /// is reloaded with the buttons over and over.
+    pub fn write(&mut self, val: u8) {
+        self.strobe = val & 0x01 != 0;
+        if self.strobe {
+            self.shift = self.buttons;
+        }
+    }
+
+    /// Returns the next button in bit 0. All reads after the eighth
+    /// return 1 on official controllers.
+    pub fn read(&mut self) -> u8 {
+        if self.strobe {
+            return self.buttons & 0x01;
+        }
+        let bit = self.shift & 0x01;
+        self.shift = (self.shift >> 1) | 0x80;
+        bit
+    }
+
+    /// Like [`read`](Controller::read), without shifting.
+    pub fn peek(&self) -> u8 {
+        if self.strobe {
+            self.buttons & 0x01
+        } else {
+            self.shift & 0x01
+        }
+    }
+}
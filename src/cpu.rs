@@ -1,9 +1,17 @@
 use crate::bus::Bus;
-use crate::mem::Memory;
-use crate::opcode::{self, AddressMode, Instruction, Opcode};
+use crate::mem::{DmaBus, Memory};
+use crate::opcode::{self, AddressMode, Instruction};
+use thiserror::Error;
 
 const STACK_ADDRESS: u16 = 0x0100;
 
+const NMI_VECTOR: u16 = 0xFFFA;
+const RESET_VECTOR: u16 = 0xFFFC;
+const IRQ_VECTOR: u16 = 0xFFFE;
+
+/// The value that XAA and LXA use by default, which matches most consoles.
+pub const DEFAULT_MAGIC: u8 = 0xEE;
+
 #[derive(Debug)]
 #[repr(u8)]
 pub enum StatusFlag {
@@ -54,811 +62,949 @@ impl Default for Registers {
     }
 }
 
-#[derive(Debug, Clone)]
-pub enum Operand {
-    Accumulator,
-    XRegister,
-    YRegister,
-    Address(u16),
-    Relative(i8),
-    Implied,
+/// The CPU executed one of the JAM opcodes and stopped fetching
+/// instructions until the next reset.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("cpu jammed by opcode ${opcode:02X} at ${pc:04X}")]
+pub struct Jam {
+    pub pc: u16,
+    pub opcode: u8,
 }
 
-impl Operand {
-    pub fn read(&self, cpu: &Cpu) -> Option<u8> {
-        match self {
-            Operand::Accumulator => Some(cpu.reg.a),
-            Operand::Address(addr) => Some(cpu.read(*addr)),
-            Operand::XRegister => Some(cpu.reg.x),
-            Operand::YRegister => Some(cpu.reg.y),
-            _ => None,
-        }
-    }
+/// The 7 cycle sequences that push the state and load a vector, BRK included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Interrupt {
+    Reset,
+    Nmi,
+    Irq,
+    Break,
+}
 
-    pub fn write(&self, cpu: &mut Cpu, val: u8) {
-        match self {
-            Operand::Accumulator => cpu.reg.a = val,
-            Operand::XRegister => cpu.reg.x = val,
-            Operand::YRegister => cpu.reg.y = val,
-            Operand::Address(addr) => cpu.write(*addr, val),
-            _ => {}
-        };
-    }
+/// How an instruction accesses its operand in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read,
+    Modify,
+    Write,
+}
 
-    pub fn absolute_addr(&self, cpu: &Cpu) -> Option<u16> {
-        match self {
-            Operand::Address(addr) => Some(*addr),
-            Operand::Relative(addr) => Some((cpu.reg.pc as i32 + *addr as i32) as u16),
-            _ => None,
+impl Access {
+    fn of(inst: &Instruction) -> Self {
+        use Instruction::*;
+        match inst {
+            ASL | LSR | ROL | ROR | INC | DEC | SLO | RLA | SRE | RRA | DCP | ISC => Access::Modify,
+            STA | STX | STY | SAX | SHA | SHX | SHY | TAS => Access::Write,
+            _ => Access::Read,
         }
     }
 }
 
-#[derive(Default)]
-pub struct Cpu {
-    pub bus: Bus,
+/// The 2A03 CPU core, which runs one cycle per [`clock`](Cpu::clock).
+/// Every cycle does exactly the bus access of the real chip, dummy
+/// reads and writes included.
+///
+/// The CPU runs on any [`DmaBus`], the NES [`Bus`] by default.
+pub struct Cpu<B = Bus> {
+    pub bus: B,
     pub reg: Registers,
-    pub cycles: u8,
-    pub cycle_count: u32,
-    pub additional_cycle: bool,
+    pub cycle_count: u64,
+    /// The level of the IRQ input, which is polled at the end of every cycle
+    /// while interrupts are enabled.
+    pub irq_line: bool,
+    /// The level of the NMI input. A rising edge requests an NMI, that
+    /// stays pending until it's handled.
+    pub nmi_line: bool,
+    /// The constant that XAA and LXA OR into the accumulator. It depends on
+    /// the chip and its temperature, common values are `$EE`, `$FF` and `$00`.
+    pub magic: u8,
+    /// Set once the CPU ran into a JAM opcode.
+    pub halted: Option<Jam>,
+
+    /// The number of cycles of the current instruction that are done,
+    /// zero at an instruction boundary.
+    step: u8,
+    opcode: u8,
+    /// Set while an interrupt sequence runs instead of an instruction.
+    sequence: Option<Interrupt>,
+    /// The level of `nmi_line` during the previous cycle.
+    prev_nmi_line: bool,
+    nmi_pending: bool,
+    irq_pending: bool,
+    /// The results of the poll in the second-to-last cycle of an instruction,
+    /// which decide whether an interrupt follows it.
+    nmi_polled: bool,
+    irq_polled: bool,
+    /// Keeps the previous poll results for the current cycle.
+    skip_poll: bool,
+    /// The address of the read in the current cycle, which the CPU keeps
+    /// repeating while it's halted for DMA.
+    last_read: Option<u16>,
+    /// The number of cycles left until the DMA read is done.
+    dma_cycles: u8,
+    /// The effective address of the operand, or the jump target.
+    addr: u16,
+    /// The zeropage pointer of the indirect addressing modes.
+    ptr: u8,
+    /// The operand of read-modify-write instructions.
+    data: u8,
+    /// Whether indexing crossed a page.
+    crossed: bool,
 }
 
-impl Cpu {
-    pub fn new(bus: Bus, reg: Registers) -> Self {
+impl<B: DmaBus + Default> Default for Cpu<B> {
+    fn default() -> Self {
+        Self::new(B::default(), Registers::default())
+    }
+}
+
+impl<B: DmaBus> Cpu<B> {
+    pub fn new(bus: B, reg: Registers) -> Self {
         Self {
             bus,
             reg,
-            cycles: 0,
             cycle_count: 0,
-            additional_cycle: false,
+            irq_line: false,
+            nmi_line: false,
+            magic: DEFAULT_MAGIC,
+            halted: None,
+            step: 0,
+            opcode: 0,
+            sequence: None,
+            prev_nmi_line: false,
+            nmi_pending: false,
+            irq_pending: false,
+            nmi_polled: false,
+            irq_polled: false,
+            skip_poll: false,
+            last_read: None,
+            dma_cycles: 0,
+            addr: 0,
+            ptr: 0,
+            data: 0,
+            crossed: false,
         }
     }
 
+    /// Whether the next cycle starts a new instruction.
+    pub fn at_instruction_boundary(&self) -> bool {
+        self.step == 0 && self.sequence.is_none() && self.dma_cycles == 0
+    }
+
+    /// Runs the CPU until the current instruction is finished, or the next
+    /// one if the CPU is at an instruction boundary.
     pub fn execute_instruction(&mut self) {
         loop {
             self.clock();
-            if self.cycles == 0 {
+            if self.at_instruction_boundary() {
                 break;
             }
         }
     }
 
+    /// Runs a single CPU cycle.
     pub fn clock(&mut self) {
-        if self.cycles > 0 {
-            self.cycles -= 1;
+        if self.at_instruction_boundary() && self.halted.is_some() {
             return;
         }
-
-        let opcode = self.fetch();
-        let (opcode, raw_opcode) = (&opcode::OPCODES[opcode as usize], opcode);
-
-        self.cycles = opcode.cycles;
-
-        self.additional_cycle = false;
-        let operand = self.fetch_operand(opcode);
-        self.execute_op(opcode, operand.clone(), raw_opcode);
-
-        if self.additional_cycle {
-            self.cycles += 1;
+        self.cycle_count += 1;
+        if self.dma_cycles > 0 {
+            self.dma_step();
+            return;
         }
+        self.last_read = None;
+        self.step += 1;
 
-        self.cycle_count += self.cycles as u32;
-        println!(
-            "Processing opcode: {:?}. reg = {:?} operand = {:?} cycles = {:?}",
-            opcode, self.reg, operand, self.cycle_count
-        );
-    }
-
-    fn execute_op(&mut self, code: &Opcode, op: Operand, raw: u8) {
-        match code.inst {
-            Instruction::ADC => self.adc(op),
-            Instruction::AND => self.and(op),
-            Instruction::ASL => self.asl(op),
-            Instruction::BCC => self.bcc(op),
-            Instruction::BCS => self.bcs(op),
-            Instruction::BEQ => self.beq(op),
-            Instruction::BIT => self.bit(op),
-            Instruction::BMI => self.bmi(op),
-            Instruction::BNE => self.bne(op),
-            Instruction::BPL => self.bpl(op),
-            Instruction::BRK => self.brk(op),
-            Instruction::BVC => self.bvc(op),
-            Instruction::BVS => self.bvs(op),
-            Instruction::CLC => self.clc(),
-            Instruction::CLD => self.cld(),
-            Instruction::CLI => self.cli(),
-            Instruction::CLV => self.clv(),
-            Instruction::CMP => self.cmp(op),
-            Instruction::CPX => self.cpx(op),
-            Instruction::CPY => self.cpy(op),
-            Instruction::DEC => self.dec(op),
-            Instruction::DEX => self.dex(),
-            Instruction::DEY => self.dey(),
-            Instruction::EOR => self.eor(op),
-            Instruction::INC => self.inc(op),
-            Instruction::INX => self.inx(),
-            Instruction::INY => self.iny(),
-            Instruction::JMP => self.jmp(op),
-            Instruction::JSR => self.jsr(op),
-            Instruction::LDA => self.lda(op),
-            Instruction::LDX => self.ldx(op),
-            Instruction::LDY => self.ldy(op),
-            Instruction::LSR => self.lsr(op),
-            Instruction::NOP => self.nop(raw),
-            Instruction::ORA => self.ora(op),
-            Instruction::PHA => self.pha(),
-            Instruction::PHP => self.php(),
-            Instruction::PLA => self.pla(),
-            Instruction::PLP => self.plp(),
-            Instruction::ROL => self.rol(op),
-            Instruction::ROR => self.ror(op),
-            Instruction::RTI => self.rti(),
-            Instruction::RTS => self.rts(),
-            Instruction::SBC => self.sbc(op),
-            Instruction::SEC => self.sec(),
-            Instruction::SED => self.sed(),
-            Instruction::SEI => self.sei(),
-            Instruction::STA => self.sta(op),
-            Instruction::STX => self.stx(op),
-            Instruction::STY => self.sty(op),
-            Instruction::TAX => self.tax(),
-            Instruction::TAY => self.tay(),
-            Instruction::TSX => self.tsx(),
-            Instruction::TXA => self.txa(),
-            Instruction::TXS => self.txs(),
-            Instruction::TYA => self.tya(),
-            Instruction::XXX => self.nop(raw),
+        let done = match self.sequence {
+            _ if self.step == 1 => {
+                self.start_instruction();
+                false
+            }
+            Some(interrupt) => self.interrupt_step(interrupt),
+            None => self.instruction_step(),
         };
-    }
+        self.poll_interrupts();
 
-    fn fetch_operand(&mut self, op: &Opcode) -> Operand {
-        match op.addr {
-            AddressMode::Accumulator => Operand::Accumulator,
-            AddressMode::Absolute => Operand::Address(self.fetch_word()),
-            AddressMode::AbsoluteXIndexed => Operand::Address(self.fetch_absolute(self.reg.x)),
-            AddressMode::AbsoluteYIndexed => Operand::Address(self.fetch_absolute(self.reg.y)),
-            AddressMode::Immediate => Operand::Address(self.fetch_immediate()),
-            AddressMode::Implied => Operand::Implied,
-            AddressMode::Indirect => Operand::Address(self.fetch_indirect()),
-            AddressMode::IndirectXIndexed => Operand::Address(self.fetch_indirect_x()),
-            AddressMode::IndirectYIndexed => Operand::Address(self.fetch_indirect_y()),
-            AddressMode::Relative => Operand::Relative(self.fetch() as i8),
-            AddressMode::Zeropage => Operand::Address(self.fetch_zeropage()),
-            AddressMode::ZeropageXIndexed => Operand::Address(self.fetch_zeropage_x()),
-            AddressMode::ZeropageYIndexed => Operand::Address(self.fetch_zeropage_y()),
+        // DMA can only halt the CPU on a read. The halt takes 3 cycles, plus
+        // one to line up with the APU, which keeps counting across a reset.
+        if self.last_read.is_some() && self.bus.dma_request().is_some() {
+            self.dma_cycles = if self.bus.apu_cycle() % 2 == 1 { 4 } else { 3 };
         }
-    }
-
-    pub fn reset(&mut self) {
-        self.reg.a = 0;
-        self.reg.x = 0;
-        self.reg.y = 0;
-        self.reg.sp = 0xFD;
-        self.reg.p = 0x0;
 
-        let addr = 0xFFFC;
-        self.reg.pc = self.read_word(0xFFFC);
-
-        self.cycles = 7;
-        self.cycle_count = 7;
-    }
-
-    pub fn irq(&mut self) {
-        if self.reg.get_flag(StatusFlag::NoInterrupts) {
-            return;
+        if done {
+            self.step = 0;
+            // The first instruction of a handler always runs before the next NMI.
+            if self.sequence.take().is_some() {
+                self.nmi_polled = false;
+            }
         }
-        self.push_word(self.reg.pc);
-
-        self.reg.set_flag(StatusFlag::Break, false);
-        self.reg.set_flag(StatusFlag::NoInterrupts, false);
-        self.push(self.reg.p);
-
-        self.reg.pc = self.read_word(0xFFFE);
-        self.cycles = 7;
-    }
-
-    pub fn nmi(&mut self) {
-        self.push_word(self.reg.pc);
-
-        self.reg.set_flag(StatusFlag::Break, false);
-        self.reg.set_flag(StatusFlag::NoInterrupts, false);
-        self.push(self.reg.p);
-
-        self.reg.pc = self.read_word(0xFFFA);
-        self.cycles = 7;
-    }
-
-    fn fetch(&mut self) -> u8 {
-        let result = self.read(self.reg.pc);
-        self.reg.pc += 1;
-        result
     }
 
-    fn fetch_word(&mut self) -> u16 {
-        let lower = self.fetch() as u16;
-        let upper = self.fetch() as u16;
-        (upper << 8) | lower
-    }
-
-    fn fetch_immediate(&mut self) -> u16 {
-        self.reg.pc += 1;
-        self.reg.pc - 1
-    }
-
-    fn fetch_absolute(&mut self, offset: u8) -> u16 {
-        let lower = self.fetch() as u16;
-        let upper = self.fetch() as u16;
-        let addr = (upper << 8) | lower;
-        let addr = addr.wrapping_add(offset as u16);
-
-        if (addr & 0xFF00) != (upper << 8) {
-            self.additional_cycle = true;
+    /// The first cycle of an instruction, which either fetches the opcode or
+    /// starts an interrupt sequence.
+    fn start_instruction(&mut self) {
+        if self.sequence.is_none() {
+            if self.nmi_polled {
+                self.sequence = Some(Interrupt::Nmi);
+            } else if self.irq_polled {
+                self.sequence = Some(Interrupt::Irq);
+            }
         }
-        addr
-    }
-
-    fn fetch_indirect(&mut self) -> u16 {
-        let lower = self.fetch() as u16;
-        let upper = self.fetch() as u16;
-
-        let ptr = (upper << 8) | lower;
-
-        let (upper, lower) = if lower == 0xFF {
-            (self.read(ptr & 0xFF00) as u16, self.read(ptr) as u16)
-        } else {
-            (self.read(ptr + 1) as u16, self.read(ptr) as u16)
-        };
 
-        let addr = (upper << 8) | lower;
-        addr
-    }
-
-    fn fetch_indirect_x(&mut self) -> u16 {
-        let ptr = self.fetch() as u16;
-
-        let x = self.reg.x as u16;
-        let lower = self.read(ptr + x & 0x00FF) as u16;
-        let upper = self.read((ptr + x + 1) & 0x00FF) as u16;
-
-        (upper << 8) | lower
-    }
-
-    fn fetch_indirect_y(&mut self) -> u16 {
-        let ptr = self.fetch() as u16;
-
-        let lower = self.read(ptr & 0xFF) as u16;
-        let upper = self.read((ptr + 1) & 0xFF) as u16;
-
-        let addr = (upper << 8) | lower;
-        let addr = addr.wrapping_add(self.reg.y as u16);
-
-        if (addr & 0xFF00) != (upper << 8) {
-            self.additional_cycle = true;
+        // Interrupts replace the opcode fetch with a dummy read.
+        match self.sequence {
+            Some(_) => {
+                self.read(self.reg.pc);
+            }
+            None => self.opcode = self.fetch(),
         }
-
-        addr
-    }
-
-    fn fetch_zeropage(&mut self) -> u16 {
-        self.fetch() as u16 & 0xFF
-    }
-
-    fn fetch_zeropage_x(&mut self) -> u16 {
-        (self.fetch() as u16 + self.reg.x as u16) & 0xFF
-    }
-
-    fn fetch_zeropage_y(&mut self) -> u16 {
-        (self.fetch() as u16 + self.reg.y as u16) & 0xFF
-    }
-
-    fn read_word(&self, addr: u16) -> u16 {
-        self.bus.read_word(addr)
     }
 
-    fn read(&self, addr: u16) -> u8 {
-        self.bus.read(addr)
-    }
-
-    fn write(&mut self, addr: u16, val: u8) {
-        self.bus.write(addr, val);
+    /// A cycle in which the CPU is halted. It repeats its last read until
+    /// the DMA read happens in the final cycle, which means that registers
+    /// like `$4016` and `$2007` see more than one read.
+    fn dma_step(&mut self) {
+        self.dma_cycles -= 1;
+        if self.dma_cycles > 0 {
+            if let Some(addr) = self.last_read {
+                self.bus.read(addr);
+            }
+        } else if let Some(addr) = self.bus.dma_request() {
+            let val = self.bus.read(addr);
+            self.bus.dma_complete(val);
+        }
     }
 
-    fn push(&mut self, val: u8) {
-        self.write(STACK_ADDRESS + self.reg.sp as u16, val);
-        self.reg.sp -= 1;
-    }
+    /// Samples the interrupt lines at the end of a cycle.
+    fn poll_interrupts(&mut self) {
+        if !self.skip_poll {
+            self.nmi_polled = self.nmi_pending;
+            self.irq_polled = self.irq_pending;
+        }
+        self.skip_poll = false;
 
-    fn push_word(&mut self, val: u16) {
-        self.push((val >> 8) as u8);
-        self.push(val as u8);
+        if self.nmi_line && !self.prev_nmi_line {
+            self.nmi_pending = true;
+        }
+        self.prev_nmi_line = self.nmi_line;
+        self.irq_pending = self.irq_line && !self.reg.get_flag(StatusFlag::NoInterrupts);
     }
 
-    fn pop(&mut self) -> u8 {
-        self.reg.sp += 1;
-        self.read(STACK_ADDRESS + self.reg.sp as u16)
-    }
+    /// Starts the reset sequence, which takes 7 cycles like an interrupt.
+    pub fn reset(&mut self) {
+        self.reg.a = 0;
+        self.reg.x = 0;
+        self.reg.y = 0;
+        self.reg.sp = 0x00;
+        self.reg.p = 0x0;
 
-    fn pop_word(&mut self) -> u16 {
-        let lower = self.pop() as u16;
-        let upper = self.pop() as u16;
-        (upper << 8) | lower
+        self.cycle_count = 0;
+        self.halted = None;
+        self.nmi_pending = false;
+        self.nmi_polled = false;
+        self.irq_pending = false;
+        self.irq_polled = false;
+        self.step = 0;
+        self.sequence = Some(Interrupt::Reset);
+    }
+
+    /// Runs the cycle `self.step` of an interrupt sequence and returns
+    /// whether the sequence is done.
+    fn interrupt_step(&mut self, interrupt: Interrupt) -> bool {
+        match self.step {
+            2 => {
+                self.read(self.reg.pc);
+                // BRK skips the padding byte after the opcode.
+                if interrupt == Interrupt::Break {
+                    self.reg.pc = self.reg.pc.wrapping_add(1);
+                }
+            }
+            3..=5 if interrupt == Interrupt::Reset => {
+                // The pushes turn into reads, so only the stack pointer changes.
+                self.read(STACK_ADDRESS + self.reg.sp as u16);
+                self.reg.sp = self.reg.sp.wrapping_sub(1);
+            }
+            3 => self.push((self.reg.pc >> 8) as u8),
+            4 => self.push(self.reg.pc as u8),
+            5 => {
+                let mut p = self.reg.p | StatusFlag::Unused as u8;
+                if interrupt == Interrupt::Break {
+                    p |= StatusFlag::Break as u8;
+                } else {
+                    p &= !(StatusFlag::Break as u8);
+                }
+                self.push(p);
+                self.reg.set_flag(StatusFlag::NoInterrupts, true);
+
+                // The vector is picked only now, so a pending NMI hijacks
+                // the sequence of BRK or an IRQ.
+                self.addr = if self.nmi_pending {
+                    self.nmi_pending = false;
+                    NMI_VECTOR
+                } else {
+                    IRQ_VECTOR
+                };
+            }
+            6 => {
+                if interrupt == Interrupt::Reset {
+                    self.reg.set_flag(StatusFlag::NoInterrupts, true);
+                    self.addr = RESET_VECTOR;
+                }
+                self.data = self.read(self.addr);
+            }
+            _ => {
+                let upper = self.read(self.addr + 1) as u16;
+                self.reg.pc = (upper << 8) | self.data as u16;
+                return true;
+            }
+        }
+        false
     }
 
-    fn adc(&mut self, op: Operand) {
-        let fetched = op.read(self).unwrap() as u16;
-        let a = self.reg.a as u16;
-        let val = a + fetched + self.reg.get_flag(StatusFlag::Carry) as u16;
+    /// Runs the cycle `self.step` of the current instruction and returns
+    /// whether the instruction is done.
+    fn instruction_step(&mut self) -> bool {
+        let opcode = &opcode::OPCODES[self.opcode as usize];
 
-        let v = !(a ^ fetched) & (a ^ val) & 0x80;
-        self.reg.set_flag(StatusFlag::Overflow, v != 0);
-        self.reg.set_flag(StatusFlag::Carry, (val & 0x100) != 0);
-        self.reg.set_flag(StatusFlag::Zero, val & 0xFF == 0);
-        self.reg.set_flag(StatusFlag::Negative, val & 0x80 != 0);
-        self.reg.a = val as u8;
-
-        self.additional_cycle &= true;
+        match opcode.inst {
+            Instruction::BRK => {
+                self.sequence = Some(Interrupt::Break);
+                self.interrupt_step(Interrupt::Break)
+            }
+            Instruction::JSR => self.jsr_step(),
+            Instruction::RTS => self.rts_step(),
+            Instruction::RTI => self.rti_step(),
+            Instruction::JMP => self.jmp_step(&opcode.addr),
+            Instruction::PHA | Instruction::PHP => self.push_step(&opcode.inst),
+            Instruction::PLA | Instruction::PLP => self.pull_step(&opcode.inst),
+            Instruction::JAM => {
+                self.read(self.reg.pc);
+                self.halted = Some(Jam {
+                    pc: self.reg.pc.wrapping_sub(1),
+                    opcode: self.opcode,
+                });
+                true
+            }
+            ref inst => match opcode.addr {
+                AddressMode::Implied => {
+                    self.read(self.reg.pc);
+                    self.execute_implied(inst);
+                    true
+                }
+                AddressMode::Accumulator => {
+                    self.read(self.reg.pc);
+                    self.reg.a = self.execute_modify(inst, self.reg.a);
+                    true
+                }
+                AddressMode::Immediate => {
+                    let val = self.fetch();
+                    self.execute_read(inst, val);
+                    true
+                }
+                AddressMode::Relative => self.branch_step(inst),
+                ref mode => self.memory_step(inst, mode),
+            },
+        }
     }
 
-    fn and(&mut self, op: Operand) {
-        let val = op.read(self).unwrap();
-        let val = self.reg.a & val;
+    /// Resolves the effective address and then accesses the operand.
+    fn memory_step(&mut self, inst: &Instruction, mode: &AddressMode) -> bool {
+        let access = Access::of(inst);
 
-        self.reg.set_flag(StatusFlag::Negative, val & 0x80 != 0);
-        self.reg.set_flag(StatusFlag::Zero, val == 0);
+        // The cycle of the first access to the operand.
+        let first = match (mode, self.step) {
+            (AddressMode::Zeropage, 2) => {
+                self.addr = self.fetch() as u16;
+                return false;
+            }
+            (AddressMode::Zeropage, _) => 3,
 
-        self.reg.a = val;
+            (AddressMode::ZeropageXIndexed, 2) | (AddressMode::ZeropageYIndexed, 2) => {
+                self.addr = self.fetch() as u16;
+                return false;
+            }
+            (AddressMode::ZeropageXIndexed, 3) | (AddressMode::ZeropageYIndexed, 3) => {
+                let index = match mode {
+                    AddressMode::ZeropageXIndexed => self.reg.x,
+                    _ => self.reg.y,
+                };
+                self.read(self.addr);
+                self.addr = (self.addr + index as u16) & 0xFF;
+                return false;
+            }
+            (AddressMode::ZeropageXIndexed, _) | (AddressMode::ZeropageYIndexed, _) => 4,
 
-        self.additional_cycle &= true;
-    }
+            (AddressMode::Absolute, 2) => {
+                self.addr = self.fetch() as u16;
+                return false;
+            }
+            (AddressMode::Absolute, 3) => {
+                self.addr |= (self.fetch() as u16) << 8;
+                return false;
+            }
+            (AddressMode::Absolute, _) => 4,
 
-    fn asl(&mut self, op: Operand) {
-        let fetched = op.read(self).unwrap();
-        let val = fetched << 1;
+            (AddressMode::AbsoluteXIndexed, 2) | (AddressMode::AbsoluteYIndexed, 2) => {
+                self.addr = self.fetch() as u16;
+                return false;
+            }
+            (AddressMode::AbsoluteXIndexed, 3) | (AddressMode::AbsoluteYIndexed, 3) => {
+                let index = match mode {
+                    AddressMode::AbsoluteXIndexed => self.reg.x,
+                    _ => self.reg.y,
+                };
+                let upper = self.fetch() as u16;
+                self.index_address(upper, index);
+                return false;
+            }
+            (AddressMode::AbsoluteXIndexed, 4) | (AddressMode::AbsoluteYIndexed, 4) => {
+                return self.fix_address(inst, access);
+            }
+            (AddressMode::AbsoluteXIndexed, _) | (AddressMode::AbsoluteYIndexed, _) => 5,
 
-        self.reg.set_flag(StatusFlag::Negative, val & 0x80 != 0);
-        self.reg.set_flag(StatusFlag::Zero, val == 0);
-        self.reg.set_flag(StatusFlag::Carry, fetched & 0x80 != 0);
+            (AddressMode::IndirectXIndexed, 2) => {
+                self.ptr = self.fetch();
+                return false;
+            }
+            (AddressMode::IndirectXIndexed, 3) => {
+                self.read(self.ptr as u16);
+                self.ptr = self.ptr.wrapping_add(self.reg.x);
+                return false;
+            }
+            (AddressMode::IndirectXIndexed, 4) => {
+                self.addr = self.read(self.ptr as u16) as u16;
+                return false;
+            }
+            (AddressMode::IndirectXIndexed, 5) => {
+                let upper = self.read(self.ptr.wrapping_add(1) as u16) as u16;
+                self.addr |= upper << 8;
+                return false;
+            }
+            (AddressMode::IndirectXIndexed, _) => 6,
 
-        op.write(self, val);
-        self.additional_cycle &= false;
-    }
+            (AddressMode::IndirectYIndexed, 2) => {
+                self.ptr = self.fetch();
+                return false;
+            }
+            (AddressMode::IndirectYIndexed, 3) => {
+                self.addr = self.read(self.ptr as u16) as u16;
+                return false;
+            }
+            (AddressMode::IndirectYIndexed, 4) => {
+                let upper = self.read(self.ptr.wrapping_add(1) as u16) as u16;
+                self.index_address(upper, self.reg.y);
+                return false;
+            }
+            (AddressMode::IndirectYIndexed, 5) => return self.fix_address(inst, access),
+            (AddressMode::IndirectYIndexed, _) => 6,
 
-    fn branch(&mut self, op: Operand, condition: bool) {
-        if condition {
-            self.cycles += 1;
-            let addr = op.absolute_addr(self).unwrap();
+            (mode, _) => unreachable!("{:?} has no memory operand", mode),
+        };
 
-            if (addr & 0xFF00) != (self.reg.pc & 0xFF00) {
-                self.cycles += 1;
+        match (access, self.step - first) {
+            (Access::Read, _) => {
+                let val = self.read(self.addr);
+                self.execute_read(inst, val);
+                true
+            }
+            (Access::Write, _) => {
+                self.store(inst);
+                true
+            }
+            (Access::Modify, 0) => {
+                self.data = self.read(self.addr);
+                false
+            }
+            (Access::Modify, 1) => {
+                // The unmodified value is written back while the ALU works.
+                self.write(self.addr, self.data);
+                self.data = self.execute_modify(inst, self.data);
+                false
+            }
+            (Access::Modify, _) => {
+                self.write(self.addr, self.data);
+                true
             }
-
-            self.reg.pc = addr;
         }
-        self.additional_cycle &= false;
-    }
-
-    fn bcc(&mut self, op: Operand) {
-        let condition = !self.reg.get_flag(StatusFlag::Carry);
-        self.branch(op, condition);
     }
 
-    fn bcs(&mut self, op: Operand) {
-        let condition = self.reg.get_flag(StatusFlag::Carry);
-        self.branch(op, condition);
+    /// Adds the index to the lower byte of the address in `self.addr`, without
+    /// carrying into `upper` yet.
+    fn index_address(&mut self, upper: u16, index: u8) {
+        let lower = self.addr + index as u16;
+        self.crossed = lower > 0xFF;
+        self.addr = (upper << 8) | (lower & 0xFF);
     }
 
-    fn beq(&mut self, op: Operand) {
-        let condition = self.reg.get_flag(StatusFlag::Zero);
-        self.branch(op, condition);
-    }
-
-    fn bit(&mut self, op: Operand) {
-        let fetched = op.read(self).unwrap();
-        let val = fetched & self.reg.a;
-
-        self.reg.set_flag(StatusFlag::Zero, val == 0);
-        self.reg.set_flag(StatusFlag::Overflow, fetched & 0x40 != 0);
-        self.reg.set_flag(StatusFlag::Negative, fetched & 0x80 != 0);
-
-        self.additional_cycle &= false;
-    }
+    /// The cycle after indexing, which reads from the address before the carry
+    /// was added. Reads that didn't cross a page are done at this point.
+    fn fix_address(&mut self, inst: &Instruction, access: Access) -> bool {
+        let val = self.read(self.addr);
+        if access == Access::Read && !self.crossed {
+            self.execute_read(inst, val);
+            return true;
+        }
 
-    fn bmi(&mut self, op: Operand) {
-        let condition = self.reg.get_flag(StatusFlag::Negative);
-        self.branch(op, condition);
+        if self.crossed {
+            self.addr = self.addr.wrapping_add(0x100);
+        }
+        false
     }
 
-    fn bne(&mut self, op: Operand) {
-        let condition = !self.reg.get_flag(StatusFlag::Zero);
-        self.branch(op, condition);
+    fn branch_step(&mut self, inst: &Instruction) -> bool {
+        match self.step {
+            2 => {
+                let offset = self.fetch() as i8;
+                self.addr = self.reg.pc.wrapping_add(offset as u16);
+                !self.branch_taken(inst)
+            }
+            3 => {
+                self.read(self.reg.pc);
+                let pc = (self.reg.pc & 0xFF00) | (self.addr & 0xFF);
+                self.reg.pc = pc;
+
+                // A taken branch that stays on the same page doesn't poll in its
+                // last cycle, so interrupts that show up in the previous cycle
+                // wait for one more instruction.
+                self.skip_poll = pc == self.addr;
+                self.skip_poll
+            }
+            _ => {
+                // The upper byte is fixed after a read from the wrong page.
+                self.read(self.reg.pc);
+                self.reg.pc = self.addr;
+                true
+            }
+        }
     }
 
-    fn bpl(&mut self, op: Operand) {
-        let condition = !self.reg.get_flag(StatusFlag::Negative);
-        self.branch(op, condition);
+    fn branch_taken(&mut self, inst: &Instruction) -> bool {
+        match inst {
+            Instruction::BCC => !self.reg.get_flag(StatusFlag::Carry),
+            Instruction::BCS => self.reg.get_flag(StatusFlag::Carry),
+            Instruction::BEQ => self.reg.get_flag(StatusFlag::Zero),
+            Instruction::BMI => self.reg.get_flag(StatusFlag::Negative),
+            Instruction::BNE => !self.reg.get_flag(StatusFlag::Zero),
+            Instruction::BPL => !self.reg.get_flag(StatusFlag::Negative),
+            Instruction::BVC => !self.reg.get_flag(StatusFlag::Overflow),
+            Instruction::BVS => self.reg.get_flag(StatusFlag::Overflow),
+            inst => unreachable!("{:?} is not a branch", inst),
+        }
     }
 
-    fn brk(&mut self, op: Operand) {
-        self.reg.set_flag(StatusFlag::NoInterrupts, true);
-
-        self.push_word(self.reg.pc);
-
-        self.reg.set_flag(StatusFlag::Break, true);
-        self.push(self.reg.p);
-        self.reg.set_flag(StatusFlag::Break, false);
-
-        self.reg.pc = self.read_word(0xFFFE);
-
-        self.additional_cycle &= false;
+    fn jmp_step(&mut self, mode: &AddressMode) -> bool {
+        match (mode, self.step) {
+            (_, 2) => {
+                self.addr = self.fetch() as u16;
+                false
+            }
+            (AddressMode::Absolute, _) => {
+                let upper = self.fetch() as u16;
+                self.reg.pc = (upper << 8) | self.addr;
+                true
+            }
+            (_, 3) => {
+                self.addr |= (self.fetch() as u16) << 8;
+                false
+            }
+            (_, 4) => {
+                self.data = self.read(self.addr);
+                false
+            }
+            _ => {
+                // The pointer doesn't carry into the upper byte.
+                let ptr = (self.addr & 0xFF00) | (self.addr.wrapping_add(1) & 0xFF);
+                let upper = self.read(ptr) as u16;
+                self.reg.pc = (upper << 8) | self.data as u16;
+                true
+            }
+        }
     }
 
-    fn bvc(&mut self, op: Operand) {
-        let condition = !self.reg.get_flag(StatusFlag::Overflow);
-        self.branch(op, condition);
+    fn jsr_step(&mut self) -> bool {
+        match self.step {
+            2 => self.addr = self.fetch() as u16,
+            3 => {
+                self.read(STACK_ADDRESS + self.reg.sp as u16);
+            }
+            4 => self.push((self.reg.pc >> 8) as u8),
+            5 => self.push(self.reg.pc as u8),
+            _ => {
+                let upper = self.read(self.reg.pc) as u16;
+                self.reg.pc = (upper << 8) | self.addr;
+                return true;
+            }
+        }
+        false
     }
 
-    fn bvs(&mut self, op: Operand) {
-        let condition = self.reg.get_flag(StatusFlag::Overflow);
-        self.branch(op, condition);
+    fn rts_step(&mut self) -> bool {
+        match self.step {
+            2 => {
+                self.read(self.reg.pc);
+            }
+            3 => {
+                self.read(STACK_ADDRESS + self.reg.sp as u16);
+            }
+            4 => self.reg.pc = self.pop() as u16,
+            5 => self.reg.pc |= (self.pop() as u16) << 8,
+            _ => {
+                self.fetch();
+                return true;
+            }
+        }
+        false
     }
 
-    fn clc(&mut self) {
-        self.reg.set_flag(StatusFlag::Carry, false);
-
-        self.additional_cycle &= false;
+    fn rti_step(&mut self) -> bool {
+        match self.step {
+            2 => {
+                self.read(self.reg.pc);
+            }
+            3 => {
+                self.read(STACK_ADDRESS + self.reg.sp as u16);
+            }
+            4 => {
+                self.reg.p = self.pop();
+                self.reg.set_flag(StatusFlag::Unused, true);
+                self.reg.set_flag(StatusFlag::Break, false);
+            }
+            5 => self.reg.pc = self.pop() as u16,
+            _ => {
+                self.reg.pc |= (self.pop() as u16) << 8;
+                return true;
+            }
+        }
+        false
     }
 
-    fn cld(&mut self) {
-        self.reg.set_flag(StatusFlag::Decimal, false);
+    fn push_step(&mut self, inst: &Instruction) -> bool {
+        if self.step == 2 {
+            self.read(self.reg.pc);
+            return false;
+        }
 
-        self.additional_cycle &= false;
+        match inst {
+            Instruction::PHA => self.push(self.reg.a),
+            _ => self.push(self.reg.p | StatusFlag::Break as u8 | StatusFlag::Unused as u8),
+        }
+        true
     }
 
-    fn cli(&mut self) {
-        self.reg.set_flag(StatusFlag::NoInterrupts, false);
-
-        self.additional_cycle &= false;
+    fn pull_step(&mut self, inst: &Instruction) -> bool {
+        match self.step {
+            2 => {
+                self.read(self.reg.pc);
+            }
+            3 => {
+                self.read(STACK_ADDRESS + self.reg.sp as u16);
+            }
+            _ => {
+                let val = self.pop();
+                if let Instruction::PLA = inst {
+                    self.reg.a = self.transfer(val);
+                } else {
+                    self.reg.p = val;
+                    self.reg.set_flag(StatusFlag::Unused, true);
+                    self.reg.set_flag(StatusFlag::Break, false);
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    fn execute_implied(&mut self, inst: &Instruction) {
+        match inst {
+            Instruction::CLC => self.reg.set_flag(StatusFlag::Carry, false),
+            Instruction::CLD => self.reg.set_flag(StatusFlag::Decimal, false),
+            Instruction::CLI => self.reg.set_flag(StatusFlag::NoInterrupts, false),
+            Instruction::CLV => self.reg.set_flag(StatusFlag::Overflow, false),
+            Instruction::SEC => self.reg.set_flag(StatusFlag::Carry, true),
+            Instruction::SED => self.reg.set_flag(StatusFlag::Decimal, true),
+            Instruction::SEI => self.reg.set_flag(StatusFlag::NoInterrupts, true),
+            Instruction::DEX => self.reg.x = self.dec(self.reg.x),
+            Instruction::DEY => self.reg.y = self.dec(self.reg.y),
+            Instruction::INX => self.reg.x = self.inc(self.reg.x),
+            Instruction::INY => self.reg.y = self.inc(self.reg.y),
+            Instruction::TAX => self.reg.x = self.transfer(self.reg.a),
+            Instruction::TAY => self.reg.y = self.transfer(self.reg.a),
+            Instruction::TSX => self.reg.x = self.transfer(self.reg.sp),
+            Instruction::TXA => self.reg.a = self.transfer(self.reg.x),
+            Instruction::TYA => self.reg.a = self.transfer(self.reg.y),
+            Instruction::TXS => self.reg.sp = self.reg.x,
+            Instruction::NOP => {}
+            inst => unreachable!("{:?} is not an implied instruction", inst),
+        }
     }
 
-    fn clv(&mut self) {
-        self.reg.set_flag(StatusFlag::Overflow, false);
-
-        self.additional_cycle &= false;
+    fn execute_read(&mut self, inst: &Instruction, val: u8) {
+        match inst {
+            Instruction::ADC => self.adc(val),
+            Instruction::AND => self.and(val),
+            Instruction::BIT => self.bit(val),
+            Instruction::CMP => self.compare(self.reg.a, val),
+            Instruction::CPX => self.compare(self.reg.x, val),
+            Instruction::CPY => self.compare(self.reg.y, val),
+            Instruction::EOR => self.eor(val),
+            Instruction::LDA => self.reg.a = self.transfer(val),
+            Instruction::LDX => self.reg.x = self.transfer(val),
+            Instruction::LDY => self.reg.y = self.transfer(val),
+            Instruction::ORA => self.ora(val),
+            Instruction::SBC => self.sbc(val),
+            Instruction::NOP => {}
+            Instruction::ALR => self.alr(val),
+            Instruction::ANC => self.anc(val),
+            Instruction::ARR => self.arr(val),
+            Instruction::AXS => self.axs(val),
+            Instruction::LAX => self.lax(val),
+            Instruction::LAS => self.las(val),
+            Instruction::LXA => self.lxa(val),
+            Instruction::XAA => self.xaa(val),
+            inst => unreachable!("{:?} is not a read instruction", inst),
+        }
     }
 
-    fn compare(&mut self, op: Operand, reg: Operand) {
-        let val = op.read(self).unwrap();
-        let reg = reg.read(self).unwrap();
-
-        let diff = (reg as u16).wrapping_sub(val as u16);
-        self.reg.set_flag(StatusFlag::Carry, reg >= val);
-        self.reg.set_flag(StatusFlag::Zero, diff & 0xFF == 0);
-        self.reg.set_flag(StatusFlag::Negative, diff & 0x80 != 0);
+    fn execute_modify(&mut self, inst: &Instruction, val: u8) -> u8 {
+        match inst {
+            Instruction::ASL => self.asl(val),
+            Instruction::DEC => self.dec(val),
+            Instruction::INC => self.inc(val),
+            Instruction::LSR => self.lsr(val),
+            Instruction::ROL => self.rol(val),
+            Instruction::ROR => self.ror(val),
+            Instruction::DCP => self.dcp(val),
+            Instruction::ISC => self.isc(val),
+            Instruction::RLA => self.rla(val),
+            Instruction::RRA => self.rra(val),
+            Instruction::SLO => self.slo(val),
+            Instruction::SRE => self.sre(val),
+            inst => unreachable!("{:?} is not a read-modify-write instruction", inst),
+        }
     }
 
-    #[inline]
-    fn cmp(&mut self, op: Operand) {
-        self.compare(op, Operand::Accumulator);
-        self.additional_cycle &= true;
+    /// Writes the register of a store instruction to `self.addr`.
+    fn store(&mut self, inst: &Instruction) {
+        let val = match inst {
+            Instruction::STA => self.reg.a,
+            Instruction::STX => self.reg.x,
+            Instruction::STY => self.reg.y,
+            Instruction::SAX => self.reg.a & self.reg.x,
+            Instruction::SHA => return self.store_high_and(self.reg.a & self.reg.x),
+            Instruction::SHX => return self.store_high_and(self.reg.x),
+            Instruction::SHY => return self.store_high_and(self.reg.y),
+            Instruction::TAS => {
+                self.reg.sp = self.reg.a & self.reg.x;
+                return self.store_high_and(self.reg.sp);
+            }
+            inst => unreachable!("{:?} is not a store instruction", inst),
+        };
+        self.write(self.addr, val);
     }
 
-    #[inline]
-    fn cpx(&mut self, op: Operand) {
-        self.compare(op, Operand::XRegister);
+    /// The store of SHA, SHX, SHY and TAS. The value is ANDed with the high
+    /// byte of the base address plus one, and if indexing crossed a page,
+    /// the value also replaces the high byte of the target address.
+    fn store_high_and(&mut self, val: u8) {
+        // The carry is already part of `self.addr` if a page was crossed.
+        let high = ((self.addr >> 8) as u8).wrapping_add(!self.crossed as u8);
+        let val = val & high;
 
-        self.additional_cycle &= false;
+        let addr = if self.crossed {
+            ((val as u16) << 8) | (self.addr & 0xFF)
+        } else {
+            self.addr
+        };
+        self.write(addr, val);
     }
 
-    #[inline]
-    fn cpy(&mut self, op: Operand) {
-        self.compare(op, Operand::YRegister);
-
-        self.additional_cycle &= false;
+    fn fetch(&mut self) -> u8 {
+        let result = self.read(self.reg.pc);
+        self.reg.pc = self.reg.pc.wrapping_add(1);
+        result
     }
 
-    fn dec(&mut self, op: Operand) {
-        let val = op.read(self).unwrap() as i16;
-        let val = val - 1;
-
-        self.reg.set_flag(StatusFlag::Zero, val == 0);
-        self.reg.set_flag(StatusFlag::Negative, val & 0x80 != 0);
-
-        op.write(self, val as u8);
-
-        self.additional_cycle &= false;
+    fn read(&mut self, addr: u16) -> u8 {
+        self.last_read = Some(addr);
+        self.bus.read(addr)
     }
 
-    #[inline]
-    fn dex(&mut self) {
-        self.dec(Operand::XRegister);
+    fn write(&mut self, addr: u16, val: u8) {
+        self.bus.write(addr, val);
     }
 
-    #[inline]
-    fn dey(&mut self) {
-        self.dec(Operand::YRegister);
+    fn push(&mut self, val: u8) {
+        self.write(STACK_ADDRESS + self.reg.sp as u16, val);
+        self.reg.sp = self.reg.sp.wrapping_sub(1);
     }
 
-    fn eor(&mut self, op: Operand) {
-        let val = op.read(self).unwrap();
-        let val = self.reg.a ^ val;
-
-        self.reg.set_flag(StatusFlag::Zero, val == 0);
-        self.reg.set_flag(StatusFlag::Negative, val & 0x80 != 0);
-
-        self.reg.a = val;
-        self.additional_cycle &= true;
+    fn pop(&mut self) -> u8 {
+        self.reg.sp = self.reg.sp.wrapping_add(1);
+        self.read(STACK_ADDRESS + self.reg.sp as u16)
     }
 
-    fn inc(&mut self, op: Operand) {
-        let val = op.read(self).unwrap();
-        let val = val.wrapping_add(1);
-
+    fn set_zero_negative(&mut self, val: u8) {
         self.reg.set_flag(StatusFlag::Zero, val == 0);
         self.reg.set_flag(StatusFlag::Negative, val & 0x80 != 0);
-
-        op.write(self, val);
-
-        self.additional_cycle &= false;
-    }
-
-    #[inline]
-    fn inx(&mut self) {
-        self.inc(Operand::XRegister);
-    }
-
-    #[inline]
-    fn iny(&mut self) {
-        self.inc(Operand::YRegister);
-    }
-
-    fn jmp(&mut self, op: Operand) {
-        self.reg.pc = op.absolute_addr(self).unwrap();
-
-        self.additional_cycle &= false;
-    }
-
-    fn jsr(&mut self, op: Operand) {
-        self.push_word(self.reg.pc - 1);
-        self.reg.pc = op.absolute_addr(self).unwrap();
-
-        self.additional_cycle &= false;
     }
 
-    fn ld_reg(&mut self, op: Operand, reg: Operand) {
-        let val = op.read(self).unwrap();
-
-        self.reg.set_flag(StatusFlag::Zero, val == 0);
-        self.reg.set_flag(StatusFlag::Negative, val & 0x80 != 0);
-
-        reg.write(self, val);
+    /// Sets the flags for a value that is loaded into a register.
+    fn transfer(&mut self, val: u8) -> u8 {
+        self.set_zero_negative(val);
+        val
     }
 
-    #[inline]
-    fn lda(&mut self, op: Operand) {
-        self.ld_reg(op, Operand::Accumulator);
-        self.additional_cycle &= true;
-    }
+    fn adc(&mut self, fetched: u8) {
+        let fetched = fetched as u16;
+        let a = self.reg.a as u16;
+        let val = a + fetched + self.reg.get_flag(StatusFlag::Carry) as u16;
 
-    #[inline]
-    fn ldx(&mut self, op: Operand) {
-        self.ld_reg(op, Operand::XRegister);
-        self.additional_cycle &= true;
+        let v = !(a ^ fetched) & (a ^ val) & 0x80;
+        self.reg.set_flag(StatusFlag::Overflow, v != 0);
+        self.reg.set_flag(StatusFlag::Carry, (val & 0x100) != 0);
+        self.reg.a = self.transfer(val as u8);
     }
 
-    #[inline]
-    fn ldy(&mut self, op: Operand) {
-        self.ld_reg(op, Operand::YRegister);
-        self.additional_cycle &= true;
+    fn sbc(&mut self, fetched: u8) {
+        self.adc(!fetched);
     }
 
-    fn lsr(&mut self, op: Operand) {
-        let fetched = op.read(self).unwrap();
-        let val = fetched >> 1;
-
-        self.reg.set_flag(StatusFlag::Negative, val & 0x80 != 0);
-        self.reg.set_flag(StatusFlag::Zero, val == 0);
-        self.reg.set_flag(StatusFlag::Carry, fetched & 0x1 != 0);
-
-        op.write(self, val);
-
-        self.additional_cycle &= false;
+    fn and(&mut self, val: u8) {
+        self.reg.a = self.transfer(self.reg.a & val);
     }
 
-    fn nop(&mut self, op: u8) {
-        self.additional_cycle &= match op {
-            0x1C | 0x3C | 0x5C | 0x7C | 0xDC | 0xFC => true,
-            _ => false,
-        }
+    fn eor(&mut self, val: u8) {
+        self.reg.a = self.transfer(self.reg.a ^ val);
     }
 
-    fn ora(&mut self, op: Operand) {
-        let val = op.read(self).unwrap();
-        let val = self.reg.a | val;
-
-        self.reg.set_flag(StatusFlag::Zero, val == 0);
-        self.reg.set_flag(StatusFlag::Negative, val & 0x80 != 0);
-
-        self.reg.a = val;
-        self.additional_cycle &= true;
+    fn ora(&mut self, val: u8) {
+        self.reg.a = self.transfer(self.reg.a | val);
     }
 
-    fn pha(&mut self) {
-        self.push(self.reg.a);
-
-        self.additional_cycle &= false;
+    fn bit(&mut self, fetched: u8) {
+        self.reg
+            .set_flag(StatusFlag::Zero, fetched & self.reg.a == 0);
+        self.reg.set_flag(StatusFlag::Overflow, fetched & 0x40 != 0);
+        self.reg.set_flag(StatusFlag::Negative, fetched & 0x80 != 0);
     }
 
-    fn php(&mut self) {
-        self.reg.set_flag(StatusFlag::Unused, true);
-        self.reg.set_flag(StatusFlag::Break, true);
-        self.push(self.reg.p);
-        self.reg.set_flag(StatusFlag::Break, false);
-
-        self.additional_cycle &= false;
+    fn compare(&mut self, reg: u8, val: u8) {
+        self.reg.set_flag(StatusFlag::Carry, reg >= val);
+        self.set_zero_negative(reg.wrapping_sub(val));
     }
 
-    fn pla(&mut self) {
-        let val = self.pop();
-        self.reg.set_flag(StatusFlag::Zero, val == 0);
-        self.reg.set_flag(StatusFlag::Negative, val & 0x80 != 0);
-        self.reg.a = val;
-
-        self.additional_cycle &= false;
+    fn asl(&mut self, fetched: u8) -> u8 {
+        self.reg.set_flag(StatusFlag::Carry, fetched & 0x80 != 0);
+        self.transfer(fetched << 1)
     }
 
-    fn plp(&mut self) {
-        let val = self.pop();
-        self.reg.p = val;
-        self.reg.set_flag(StatusFlag::Unused, true);
-        self.reg.set_flag(StatusFlag::Break, false);
-
-        self.additional_cycle &= false;
+    fn lsr(&mut self, fetched: u8) -> u8 {
+        self.reg.set_flag(StatusFlag::Carry, fetched & 0x01 != 0);
+        self.transfer(fetched >> 1)
     }
 
-    fn rol(&mut self, op: Operand) {
-        let fetched = op.read(self).unwrap();
+    fn rol(&mut self, fetched: u8) -> u8 {
         let carry = self.reg.get_flag(StatusFlag::Carry) as u8;
-        let val = (fetched << 1) | carry;
-
         self.reg.set_flag(StatusFlag::Carry, fetched & 0x80 != 0);
-        self.reg.set_flag(StatusFlag::Zero, val == 0);
-        self.reg.set_flag(StatusFlag::Negative, val & 0x80 != 0);
-
-        op.write(self, val);
-
-        self.additional_cycle &= false;
+        self.transfer((fetched << 1) | carry)
     }
 
-    fn ror(&mut self, op: Operand) {
-        let fetched = op.read(self).unwrap() as u16;
+    fn ror(&mut self, fetched: u8) -> u8 {
         let carry = self.reg.get_flag(StatusFlag::Carry) as u8;
-        let val = (self.reg.a >> 1) | (carry << 7);
-
         self.reg.set_flag(StatusFlag::Carry, fetched & 0x01 != 0);
-        self.reg.set_flag(StatusFlag::Zero, val == 0);
-        self.reg.set_flag(StatusFlag::Negative, carry != 0);
-
-        op.write(self, val);
-
-        self.additional_cycle &= false;
+        self.transfer((fetched >> 1) | (carry << 7))
     }
 
-    fn rti(&mut self) {
-        self.reg.p = self.pop();
-        self.reg.set_flag(StatusFlag::Unused, true);
-        self.reg.pc = self.pop_word();
-
-        self.additional_cycle &= false;
+    fn dec(&mut self, val: u8) -> u8 {
+        self.transfer(val.wrapping_sub(1))
     }
 
-    fn rts(&mut self) {
-        self.reg.pc = self.pop_word() + 1;
-
-        self.additional_cycle &= false;
+    fn inc(&mut self, val: u8) -> u8 {
+        self.transfer(val.wrapping_add(1))
     }
 
-    fn sbc(&mut self, op: Operand) {
-        let fetched = op.read(self).unwrap() as u16;
-        let a = self.reg.a as u16;
-        let val = a + (!fetched & 0xFF) + self.reg.get_flag(StatusFlag::Carry) as u16;
+    // Unofficial instructions, most of them combine two official ones.
 
-        let v = (a ^ fetched) & (a ^ val) & 0x80;
-        self.reg.set_flag(StatusFlag::Overflow, v != 0);
-        self.reg.set_flag(StatusFlag::Carry, (val & 0x100) != 0);
-        self.reg.set_flag(StatusFlag::Zero, val & 0xFF == 0);
-        self.reg.set_flag(StatusFlag::Negative, val & 0x80 != 0);
-        self.reg.a = val as u8;
-
-        self.additional_cycle &= true;
+    fn alr(&mut self, val: u8) {
+        self.reg.a = self.lsr(self.reg.a & val);
     }
 
-    fn sec(&mut self) {
-        self.reg.set_flag(StatusFlag::Carry, true);
-
-        self.additional_cycle &= false;
+    fn anc(&mut self, val: u8) {
+        self.and(val);
+        let negative = self.reg.get_flag(StatusFlag::Negative);
+        self.reg.set_flag(StatusFlag::Carry, negative);
     }
 
-    fn sed(&mut self) {
-        self.reg.set_flag(StatusFlag::Decimal, true);
-
-        self.additional_cycle &= false;
+    fn arr(&mut self, val: u8) {
+        let val = self.ror(self.reg.a & val);
+        self.reg.set_flag(StatusFlag::Carry, val & 0x40 != 0);
+        self.reg
+            .set_flag(StatusFlag::Overflow, ((val >> 6) ^ (val >> 5)) & 0x01 != 0);
+        self.reg.a = val;
     }
 
-    fn sei(&mut self) {
-        self.reg.set_flag(StatusFlag::NoInterrupts, true);
-
-        self.additional_cycle &= false;
+    fn axs(&mut self, val: u8) {
+        let reg = self.reg.a & self.reg.x;
+        self.compare(reg, val);
+        self.reg.x = reg.wrapping_sub(val);
     }
 
-    fn sta(&mut self, op: Operand) {
-        let val = self.reg.a;
-        op.write(self, val);
-
-        self.additional_cycle &= false;
+    fn dcp(&mut self, val: u8) -> u8 {
+        let val = val.wrapping_sub(1);
+        self.compare(self.reg.a, val);
+        val
     }
 
-    fn stx(&mut self, op: Operand) {
-        let val = self.reg.x;
-        op.write(self, val);
-
-        self.additional_cycle &= false;
+    fn isc(&mut self, val: u8) -> u8 {
+        let val = val.wrapping_add(1);
+        self.sbc(val);
+        val
     }
 
-    fn sty(&mut self, op: Operand) {
-        let val = self.reg.y;
-        op.write(self, val);
-
-        self.additional_cycle &= false;
+    fn lax(&mut self, val: u8) {
+        self.reg.a = self.transfer(val);
+        self.reg.x = val;
     }
 
-    fn tax(&mut self) {
-        self.reg.x = self.reg.a;
-        self.reg.set_flag(StatusFlag::Zero, self.reg.x == 0);
-        self.reg
-            .set_flag(StatusFlag::Negative, self.reg.x & 0x80 != 0);
-
-        self.additional_cycle &= false;
+    fn rla(&mut self, val: u8) -> u8 {
+        let val = self.rol(val);
+        self.and(val);
+        val
     }
 
-    fn tay(&mut self) {
-        self.reg.y = self.reg.a;
-        self.reg.set_flag(StatusFlag::Zero, self.reg.y == 0);
-        self.reg
-            .set_flag(StatusFlag::Negative, self.reg.y & 0x80 != 0);
-
-        self.additional_cycle &= false;
+    fn rra(&mut self, val: u8) -> u8 {
+        let val = self.ror(val);
+        self.adc(val);
+        val
     }
 
-    fn tsx(&mut self) {
-        self.reg.x = self.reg.sp;
-        self.reg.set_flag(StatusFlag::Zero, self.reg.x == 0);
-        self.reg
-            .set_flag(StatusFlag::Negative, self.reg.x & 0x80 != 0);
-
-        self.additional_cycle &= false;
+    fn slo(&mut self, val: u8) -> u8 {
+        let val = self.asl(val);
+        self.ora(val);
+        val
     }
 
-    fn txa(&mut self) {
-        self.reg.a = self.reg.x;
-        self.reg.set_flag(StatusFlag::Zero, self.reg.a == 0);
-        self.reg
-            .set_flag(StatusFlag::Negative, self.reg.a & 0x80 != 0);
-
-        self.additional_cycle &= false;
+    fn sre(&mut self, val: u8) -> u8 {
+        let val = self.lsr(val);
+        self.eor(val);
+        val
     }
 
-    fn txs(&mut self) {
-        self.reg.sp = self.reg.x;
+    // Unstable instructions
 
-        self.additional_cycle &= false;
+    fn las(&mut self, val: u8) {
+        let val = self.transfer(val & self.reg.sp);
+        self.reg.sp = val;
+        self.reg.x = val;
+        self.reg.a = val;
     }
 
-    fn tya(&mut self) {
-        self.reg.a = self.reg.y;
-        self.reg.set_flag(StatusFlag::Zero, self.reg.a == 0);
-        self.reg
-            .set_flag(StatusFlag::Negative, self.reg.a & 0x80 != 0);
+    fn lxa(&mut self, val: u8) {
+        let val = self.transfer((self.reg.a | self.magic) & val);
+        self.reg.x = val;
+        self.reg.a = val;
+    }
 
-        self.additional_cycle &= false;
+    fn xaa(&mut self, val: u8) {
+        self.reg.a = self.transfer((self.reg.a | self.magic) & self.reg.x & val);
     }
 }
@@ -1,7 +1,15 @@
 #![allow(unused)]
 
+pub mod apu;
+pub mod blip;
 pub mod bus;
 pub mod cartridge;
+pub mod controller;
 pub mod cpu;
+pub mod mapper;
 pub mod mem;
+pub mod mixer;
+pub mod nes;
 pub mod opcode;
+pub mod ppu;
+pub mod wav;
@@ -1,3 +1,45 @@
+use nesmu::{cartridge::Cartridge, nes::Nes};
+use std::env;
+use std::process;
+
+const USAGE: &str = "usage: nesmu <rom> --wav <file> [--frames <n>] [--stems]";
+
+/// The number of frames that are recorded by default, about 10 seconds.
+const DEFAULT_FRAMES: u64 = 600;
+
 fn main() {
-    println!("nesmu");
+    let mut args = env::args().skip(1);
+    let mut rom = None;
+    let mut wav = None;
+    let mut frames = DEFAULT_FRAMES;
+    let mut stems = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--wav" => wav = args.next(),
+            "--frames" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => frames = n,
+                None => fail(USAGE),
+            },
+            "--stems" => stems = true,
+            _ if rom.is_none() => rom = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+    let (rom, wav) = match (rom, wav) {
+        (Some(rom), Some(wav)) => (rom, wav),
+        _ => fail(USAGE),
+    };
+
+    let cartridge = Cartridge::open(&rom).unwrap_or_else(|e| fail(&format!("{}: {}", rom, e)));
+    let mut nes = Nes::new(cartridge);
+    nes.reset();
+    if let Err(e) = nes.record_wav(&wav, frames, stems) {
+        fail(&format!("{}: {}", wav, e));
+    }
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    process::exit(1);
 }
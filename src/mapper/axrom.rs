@@ -0,0 +1,53 @@
+use super::{Mapped, Mapper};
+use crate::cartridge::{CartridgeHeader, Mirroring};
+
+/// The AxROM boards (mapper 7).
+///
+/// A switchable 32 KiB PRG bank and single screen mirroring,
+/// where bit 4 of the bank register selects the nametable.
+#[derive(Debug)]
+pub struct Axrom {
+    bank: u8,
+    /// The number of 32 KiB PRG banks.
+    prg_banks: usize,
+}
+
+impl Axrom {
+    pub fn new(header: &CartridgeHeader) -> Self {
+        Self {
+            bank: 0,
+            prg_banks: (header.prg_rom_bytes() / 0x8000).max(1),
+        }
+    }
+}
+
+impl Mapper for Axrom {
+    fn map_prg_read(&self, addr: u16) -> Mapped {
+        match addr {
+            0x8000..=0xFFFF => {
+                let bank = (self.bank as usize & 0x07) % self.prg_banks;
+                Mapped::PrgRom(bank * 0x8000 + (addr as usize & 0x7FFF))
+            }
+            _ => Mapped::None,
+        }
+    }
+
+    fn map_prg_write(&mut self, addr: u16, val: u8) -> Mapped {
+        if addr >= 0x8000 {
+            self.bank = val;
+        }
+        Mapped::None
+    }
+
+    fn map_chr(&self, addr: u16) -> Mapped {
+        Mapped::Chr(addr as usize)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        if self.bank & 0x10 != 0 {
+            Mirroring::SingleScreenUpper
+        } else {
+            Mirroring::SingleScreenLower
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use super::{Mapped, Mapper};
+use crate::cartridge::{CartridgeHeader, Mirroring};
+
+/// The BNROM board and the NINA-001 board, which share mapper 34.
+///
+/// BNROM has a single 32 KiB PRG bank register at `$8000-$FFFF` and 8 KiB of CHR.
+/// NINA-001 has its registers at `$7FFD-$7FFF` for the PRG bank and two 4 KiB
+/// CHR banks. NES 2.0 headers tell them apart by the submapper (1 is NINA-001,
+/// 2 is BNROM), otherwise more than 8 KiB of CHR-ROM means NINA-001.
+#[derive(Debug)]
+pub struct Bnrom {
+    nina: bool,
+    prg_bank: u8,
+    chr_banks: [u8; 2],
+    /// The number of 32 KiB PRG banks.
+    prg_banks: usize,
+    /// The number of 4 KiB CHR banks.
+    chr_bank_count: usize,
+    mirroring: Mirroring,
+}
+
+impl Bnrom {
+    pub fn new(header: &CartridgeHeader) -> Self {
+        let nina = match header.submapper() {
+            1 => true,
+            2 => false,
+            _ => header.chr_rom_bytes() > 0x2000,
+        };
+        Self {
+            nina,
+            prg_bank: 0,
+            chr_banks: [0, 1],
+            prg_banks: (header.prg_rom_bytes() / 0x8000).max(1),
+            chr_bank_count: (header.chr_rom_bytes() / 0x2000).max(1) * 2,
+            mirroring: header.mirroring(),
+        }
+    }
+}
+
+impl Mapper for Bnrom {
+    fn map_prg_read(&self, addr: u16) -> Mapped {
+        match addr {
+            // NINA-001 has 8 KiB of PRG-RAM.
+            0x6000..=0x7FFF if self.nina => Mapped::PrgRam(addr as usize & 0x1FFF),
+            0x8000..=0xFFFF => {
+                let bank = self.prg_bank as usize % self.prg_banks;
+                Mapped::PrgRom(bank * 0x8000 + (addr as usize & 0x7FFF))
+            }
+            _ => Mapped::None,
+        }
+    }
+
+    fn map_prg_write(&mut self, addr: u16, val: u8) -> Mapped {
+        match addr {
+            0x6000..=0x7FFF if self.nina => {
+                match addr {
+                    0x7FFD => self.prg_bank = val & 0x01,
+                    0x7FFE => self.chr_banks[0] = val & 0x0F,
+                    0x7FFF => self.chr_banks[1] = val & 0x0F,
+                    _ => {}
+                }
+                Mapped::PrgRam(addr as usize & 0x1FFF)
+            }
+            0x8000..=0xFFFF if !self.nina => {
+                self.prg_bank = val;
+                Mapped::None
+            }
+            _ => Mapped::None,
+        }
+    }
+
+    fn map_chr(&self, addr: u16) -> Mapped {
+        if !self.nina {
+            return Mapped::Chr(addr as usize);
+        }
+
+        let bank = self.chr_banks[addr as usize >> 12] as usize % self.chr_bank_count;
+        Mapped::Chr(bank * 0x1000 + (addr as usize & 0x0FFF))
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn bus_conflicts(&self) -> bool {
+        !self.nina
+    }
+}
@@ -0,0 +1,55 @@
+use super::{Mapped, Mapper};
+use crate::cartridge::{CartridgeHeader, Mirroring};
+
+/// The CNROM board (mapper 3).
+///
+/// Fixed PRG like NROM and a switchable 8 KiB CHR bank.
+#[derive(Debug)]
+pub struct Cnrom {
+    chr_bank: u8,
+    prg_mask: usize,
+    /// The number of 8 KiB CHR banks.
+    chr_banks: usize,
+    mirroring: Mirroring,
+}
+
+impl Cnrom {
+    pub fn new(header: &CartridgeHeader) -> Self {
+        let prg_size = header.prg_rom_bytes().max(0x4000);
+        Self {
+            chr_bank: 0,
+            prg_mask: prg_size - 1,
+            chr_banks: (header.chr_rom_bytes() / 0x2000).max(1),
+            mirroring: header.mirroring(),
+        }
+    }
+}
+
+impl Mapper for Cnrom {
+    fn map_prg_read(&self, addr: u16) -> Mapped {
+        match addr {
+            0x8000..=0xFFFF => Mapped::PrgRom(addr as usize & self.prg_mask),
+            _ => Mapped::None,
+        }
+    }
+
+    fn map_prg_write(&mut self, addr: u16, val: u8) -> Mapped {
+        if addr >= 0x8000 {
+            self.chr_bank = val;
+        }
+        Mapped::None
+    }
+
+    fn map_chr(&self, addr: u16) -> Mapped {
+        let bank = self.chr_bank as usize % self.chr_banks;
+        Mapped::Chr(bank * 0x2000 + addr as usize)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn bus_conflicts(&self) -> bool {
+        true
+    }
+}
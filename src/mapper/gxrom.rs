@@ -0,0 +1,59 @@
+use super::{Mapped, Mapper};
+use crate::cartridge::{CartridgeHeader, Mirroring};
+
+/// The GxROM and MxROM boards (mapper 66).
+///
+/// Bits 4-5 of the latch select a 32 KiB PRG bank,
+/// bits 0-1 select an 8 KiB CHR bank.
+#[derive(Debug)]
+pub struct Gxrom {
+    latch: u8,
+    /// The number of 32 KiB PRG banks.
+    prg_banks: usize,
+    /// The number of 8 KiB CHR banks.
+    chr_banks: usize,
+    mirroring: Mirroring,
+}
+
+impl Gxrom {
+    pub fn new(header: &CartridgeHeader) -> Self {
+        Self {
+            latch: 0,
+            prg_banks: (header.prg_rom_bytes() / 0x8000).max(1),
+            chr_banks: (header.chr_rom_bytes() / 0x2000).max(1),
+            mirroring: header.mirroring(),
+        }
+    }
+}
+
+impl Mapper for Gxrom {
+    fn map_prg_read(&self, addr: u16) -> Mapped {
+        match addr {
+            0x8000..=0xFFFF => {
+                let bank = ((self.latch as usize >> 4) & 0x03) % self.prg_banks;
+                Mapped::PrgRom(bank * 0x8000 + (addr as usize & 0x7FFF))
+            }
+            _ => Mapped::None,
+        }
+    }
+
+    fn map_prg_write(&mut self, addr: u16, val: u8) -> Mapped {
+        if addr >= 0x8000 {
+            self.latch = val;
+        }
+        Mapped::None
+    }
+
+    fn map_chr(&self, addr: u16) -> Mapped {
+        let bank = (self.latch as usize & 0x03) % self.chr_banks;
+        Mapped::Chr(bank * 0x2000 + addr as usize)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn bus_conflicts(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,186 @@
+use super::{Mapped, Mapper};
+use crate::cartridge::{CartridgeHeader, Mirroring};
+
+/// The SxROM boards with the MMC1 (mapper 1).
+///
+/// All registers are loaded through a 5-bit serial shift register.
+/// The 512 KiB SUROM and SXROM boards use bit 4 of the CHR bank
+/// registers to select the outer 256 KiB PRG bank, and SXROM uses
+/// bits 2-3 to select the 8 KiB PRG-RAM bank. In 4 KiB CHR mode these
+/// lines come from whichever CHR register the PPU used last.
+#[derive(Debug)]
+pub struct Mmc1 {
+    shift: u8,
+    shift_count: u8,
+    control: u8,
+    chr_bank_0: u8,
+    chr_bank_1: u8,
+    prg_bank: u8,
+    /// The number of 16 KiB PRG banks.
+    prg_banks: usize,
+    /// The number of 4 KiB CHR banks.
+    chr_banks: usize,
+    /// The number of 8 KiB PRG-RAM banks.
+    prg_ram_banks: usize,
+    /// The last state of PPU A12, which picks the CHR bank register.
+    a12: bool,
+    cycle: u64,
+    last_write: Option<u64>,
+}
+
+impl Mmc1 {
+    pub fn new(header: &CartridgeHeader) -> Self {
+        Self {
+            shift: 0,
+            shift_count: 0,
+            // The last PRG bank is fixed at `$C000` on power up.
+            control: 0x0C,
+            chr_bank_0: 0,
+            chr_bank_1: 0,
+            prg_bank: 0,
+            prg_banks: (header.prg_rom_bytes() / 0x4000).max(1),
+            chr_banks: (header.chr_rom_bytes() / 0x2000).max(1) * 2,
+            prg_ram_banks: ((header.prg_ram_bytes() + header.prg_nvram_bytes()) / 0x2000).max(1),
+            a12: false,
+            cycle: 0,
+            last_write: None,
+        }
+    }
+
+    /// The CHR bank register that drives the PRG-RAM bank and the outer
+    /// PRG bank.
+    fn chr_bank(&self) -> u8 {
+        if self.control & 0x10 != 0 && self.a12 {
+            self.chr_bank_1
+        } else {
+            self.chr_bank_0
+        }
+    }
+
+    fn prg_ram_enabled(&self) -> bool {
+        self.prg_bank & 0x10 == 0
+    }
+
+    fn map_prg_ram(&self, addr: u16) -> Mapped {
+        if !self.prg_ram_enabled() {
+            return Mapped::None;
+        }
+        // SOROM's two banks hang off bit 3, SXROM's four off bits 2-3. The
+        // battery-backed bank comes after the volatile one, like in
+        // `Cartridge::nvram_range`.
+        let bank = match self.prg_ram_banks {
+            1 => 0,
+            2 => (self.chr_bank() as usize >> 3) & 0x01,
+            _ => ((self.chr_bank() as usize >> 2) & 0x03) % self.prg_ram_banks,
+        };
+        Mapped::PrgRam(bank * 0x2000 + (addr as usize & 0x1FFF))
+    }
+
+    fn write_register(&mut self, addr: u16, val: u8) {
+        // The serial port ignores all but the first of two writes on
+        // consecutive cycles, like the ones of read-modify-write instructions.
+        let consecutive = self.last_write == Some(self.cycle.wrapping_sub(1));
+        self.last_write = Some(self.cycle);
+        if consecutive {
+            return;
+        }
+
+        if val & 0x80 != 0 {
+            self.shift = 0;
+            self.shift_count = 0;
+            self.control |= 0x0C;
+            return;
+        }
+
+        self.shift |= (val & 0x01) << self.shift_count;
+        self.shift_count += 1;
+        if self.shift_count < 5 {
+            return;
+        }
+
+        let val = self.shift;
+        match (addr >> 13) & 0x03 {
+            0 => self.control = val,
+            1 => self.chr_bank_0 = val,
+            2 => self.chr_bank_1 = val,
+            _ => self.prg_bank = val,
+        }
+        self.shift = 0;
+        self.shift_count = 0;
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn map_prg_read(&self, addr: u16) -> Mapped {
+        match addr {
+            0x6000..=0x7FFF => self.map_prg_ram(addr),
+            0x8000..=0xFFFF => {
+                let outer = if self.prg_banks > 16 {
+                    self.chr_bank() as usize & 0x10
+                } else {
+                    0
+                };
+                let bank = self.prg_bank as usize & 0x0F;
+                let high = addr >= 0xC000;
+                let bank = match (self.control >> 2) & 0x03 {
+                    // 32 KiB mode, the lowest bit of the bank number is ignored.
+                    0 | 1 => (bank & 0x0E) | high as usize,
+                    // The first bank is fixed at `$8000`.
+                    2 if high => bank,
+                    2 => 0,
+                    // The last bank is fixed at `$C000`.
+                    _ if high => 0x0F,
+                    _ => bank,
+                };
+                let bank = (outer | bank) % self.prg_banks;
+                Mapped::PrgRom(bank * 0x4000 + (addr as usize & 0x3FFF))
+            }
+            _ => Mapped::None,
+        }
+    }
+
+    fn map_prg_write(&mut self, addr: u16, val: u8) -> Mapped {
+        match addr {
+            0x6000..=0x7FFF => self.map_prg_ram(addr),
+            0x8000..=0xFFFF => {
+                self.write_register(addr, val);
+                Mapped::None
+            }
+            _ => Mapped::None,
+        }
+    }
+
+    fn map_chr(&self, addr: u16) -> Mapped {
+        let high = addr >= 0x1000;
+        let bank = if self.control & 0x10 != 0 {
+            // Two separate 4 KiB banks.
+            if high {
+                self.chr_bank_1
+            } else {
+                self.chr_bank_0
+            }
+        } else {
+            // A single 8 KiB bank, the lowest bit is ignored.
+            (self.chr_bank_0 & 0x1E) | high as u8
+        };
+        let bank = bank as usize % self.chr_banks;
+        Mapped::Chr(bank * 0x1000 + (addr as usize & 0x0FFF))
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0x03 {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+
+    fn ppu_address(&mut self, addr: u16) {
+        self.a12 = addr & 0x1000 != 0;
+    }
+
+    fn clock(&mut self) {
+        self.cycle = self.cycle.wrapping_add(1);
+    }
+}
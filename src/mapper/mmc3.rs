@@ -0,0 +1,184 @@
+use super::{Mapped, Mapper};
+use crate::cartridge::{CartridgeHeader, Mirroring};
+
+/// The number of CPU cycles A12 has to stay low, before a rising edge
+/// clocks the scanline counter.
+const A12_FILTER_CYCLES: u64 = 3;
+
+/// The TxROM boards with the MMC3 (mapper 4).
+///
+/// The scanline counter is clocked by rising edges of the PPU address line A12,
+/// which happen once per scanline if the background uses the pattern table at
+/// `$0000` and the sprites the one at `$1000`, or the other way around.
+#[derive(Debug)]
+pub struct Mmc3 {
+    bank_select: u8,
+    banks: [u8; 8],
+    mirroring: Mirroring,
+    prg_ram_enabled: bool,
+    prg_ram_protected: bool,
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq: bool,
+    a12: bool,
+    /// The CPU cycle at which A12 went low the last time.
+    a12_low_since: u64,
+    cycle: u64,
+    /// The number of 8 KiB PRG banks.
+    prg_banks: usize,
+    /// The number of 1 KiB CHR banks.
+    chr_banks: usize,
+}
+
+impl Mmc3 {
+    pub fn new(header: &CartridgeHeader) -> Self {
+        Self {
+            bank_select: 0,
+            banks: [0; 8],
+            mirroring: header.mirroring(),
+            prg_ram_enabled: true,
+            prg_ram_protected: false,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq: false,
+            a12: false,
+            a12_low_since: 0,
+            cycle: 0,
+            prg_banks: (header.prg_rom_bytes() / 0x4000).max(1) * 2,
+            chr_banks: (header.chr_rom_bytes() / 0x2000).max(1) * 8,
+        }
+    }
+
+    fn map_prg_ram(&self, addr: u16) -> Mapped {
+        if self.prg_ram_enabled {
+            Mapped::PrgRam(addr as usize & 0x1FFF)
+        } else {
+            Mapped::None
+        }
+    }
+
+    fn write_register(&mut self, addr: u16, val: u8) {
+        let even = addr & 0x01 == 0;
+        match addr {
+            0x8000..=0x9FFF if even => self.bank_select = val,
+            0x8000..=0x9FFF => self.banks[self.bank_select as usize & 0x07] = val,
+            // Four-screen boards have the mirroring hardwired.
+            0xA000..=0xBFFF if even && self.mirroring == Mirroring::FourScreen => {}
+            0xA000..=0xBFFF if even => {
+                self.mirroring = if val & 0x01 == 0 {
+                    Mirroring::Vertical
+                } else {
+                    Mirroring::Horizontal
+                }
+            }
+            0xA000..=0xBFFF => {
+                self.prg_ram_enabled = val & 0x80 != 0;
+                self.prg_ram_protected = val & 0x40 != 0;
+            }
+            0xC000..=0xDFFF if even => self.irq_latch = val,
+            0xC000..=0xDFFF => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            0xE000..=0xFFFF if even => {
+                self.irq_enabled = false;
+                self.irq = false;
+            }
+            _ => self.irq_enabled = true,
+        }
+    }
+
+    fn clock_irq_counter(&mut self) {
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+
+        if self.irq_counter == 0 && self.irq_enabled {
+            self.irq = true;
+        }
+    }
+}
+
+impl Mapper for Mmc3 {
+    fn map_prg_read(&self, addr: u16) -> Mapped {
+        match addr {
+            0x6000..=0x7FFF => self.map_prg_ram(addr),
+            0x8000..=0xFFFF => {
+                let second_last = self.prg_banks - 2;
+                let swapped = self.bank_select & 0x40 != 0;
+                let bank = match (addr >> 13) & 0x03 {
+                    0 if swapped => second_last,
+                    0 => self.banks[6] as usize,
+                    1 => self.banks[7] as usize,
+                    2 if swapped => self.banks[6] as usize,
+                    2 => second_last,
+                    _ => self.prg_banks - 1,
+                };
+                let bank = (bank & 0x3F) % self.prg_banks;
+                Mapped::PrgRom(bank * 0x2000 + (addr as usize & 0x1FFF))
+            }
+            _ => Mapped::None,
+        }
+    }
+
+    fn map_prg_write(&mut self, addr: u16, val: u8) -> Mapped {
+        match addr {
+            0x6000..=0x7FFF if self.prg_ram_protected => Mapped::None,
+            0x6000..=0x7FFF => self.map_prg_ram(addr),
+            0x8000..=0xFFFF => {
+                self.write_register(addr, val);
+                Mapped::None
+            }
+            _ => Mapped::None,
+        }
+    }
+
+    fn map_chr(&self, addr: u16) -> Mapped {
+        // With CHR inversion, the two 2 KiB banks are at `$1000`.
+        let addr = if self.bank_select & 0x80 != 0 {
+            addr ^ 0x1000
+        } else {
+            addr
+        };
+
+        let bank = match addr >> 10 {
+            0 => self.banks[0] & 0xFE,
+            1 => self.banks[0] | 0x01,
+            2 => self.banks[1] & 0xFE,
+            3 => self.banks[1] | 0x01,
+            n => self.banks[n as usize - 2],
+        };
+        let bank = bank as usize % self.chr_banks;
+        Mapped::Chr(bank * 0x400 + (addr as usize & 0x3FF))
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn irq(&self) -> bool {
+        self.irq
+    }
+
+    fn ppu_address(&mut self, addr: u16) {
+        let a12 = addr & 0x1000 != 0;
+        if a12 && !self.a12 && self.cycle - self.a12_low_since >= A12_FILTER_CYCLES {
+            self.clock_irq_counter();
+        }
+        if !a12 && self.a12 {
+            self.a12_low_since = self.cycle;
+        }
+        self.a12 = a12;
+    }
+
+    fn clock(&mut self) {
+        self.cycle += 1;
+    }
+}
@@ -0,0 +1,124 @@
+//! The boards inside a cartridge, which decide where the CPU and PPU accesses end up.
+
+mod axrom;
+mod bnrom;
+mod cnrom;
+mod color_dreams;
+mod gxrom;
+mod mmc1;
+mod mmc3;
+mod nrom;
+mod uxrom;
+
+pub use axrom::Axrom;
+pub use bnrom::Bnrom;
+pub use cnrom::Cnrom;
+pub use color_dreams::ColorDreams;
+pub use gxrom::Gxrom;
+pub use mmc1::Mmc1;
+pub use mmc3::Mmc3;
+pub use nrom::Nrom;
+pub use uxrom::Uxrom;
+
+use crate::cartridge::{CartridgeHeader, Mirroring};
+use std::fmt::Debug;
+
+/// The memory an access to the cartridge is routed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mapped {
+    /// An offset into the PRG-ROM.
+    PrgRom(usize),
+    /// An offset into the PRG-RAM.
+    PrgRam(usize),
+    /// An offset into the CHR-ROM or CHR-RAM.
+    Chr(usize),
+    /// An offset into the 2 KiB nametable RAM inside the console.
+    Vram(usize),
+    /// Nothing on the cartridge responds to the access.
+    None,
+}
+
+/// A cartridge board.
+///
+/// A mapper only translates addresses and keeps the state of its registers,
+/// the memory itself is owned by the [`Cartridge`](crate::cartridge::Cartridge).
+pub trait Mapper: Debug {
+    /// Maps a CPU read in the range `$4020-$FFFF`.
+    fn map_prg_read(&self, addr: u16) -> Mapped;
+
+    /// Maps a CPU write in the range `$4020-$FFFF`, which may also
+    /// update the registers of the mapper.
+    fn map_prg_write(&mut self, addr: u16, val: u8) -> Mapped;
+
+    /// Maps a PPU access to the pattern tables at `$0000-$1FFF`.
+    fn map_chr(&self, addr: u16) -> Mapped;
+
+    /// Maps a PPU access to the nametables at `$2000-$3EFF`.
+    fn map_nametable(&self, addr: u16) -> Mapped {
+        Mapped::Vram(self.mirroring().nametable_index(addr))
+    }
+
+    /// The current nametable arrangement.
+    fn mirroring(&self) -> Mirroring;
+
+    /// Whether the PRG-ROM keeps driving the data bus during writes, so the
+    /// written value is ANDed with the ROM byte at the same address.
+    fn bus_conflicts(&self) -> bool {
+        false
+    }
+
+    /// The state of the IRQ output of the board.
+    fn irq(&self) -> bool {
+        false
+    }
+
+    /// Called for every address the PPU puts on its address bus.
+    fn ppu_address(&mut self, addr: u16) {}
+
+    /// Called once every CPU cycle.
+    fn clock(&mut self) {}
+}
+
+/// Creates the board that is described by the mapper number in the header.
+///
+/// Returns `None` if the mapper is not supported.
+pub fn from_header(header: &CartridgeHeader) -> Option<Box<dyn Mapper>> {
+    let mapper: Box<dyn Mapper> = match header.mapper() {
+        0 => Box::new(Nrom::new(header)),
+        1 => Box::new(Mmc1::new(header)),
+        2 => Box::new(Uxrom::new(header)),
+        3 => Box::new(Cnrom::new(header)),
+        4 => Box::new(Mmc3::new(header)),
+        7 => Box::new(Axrom::new(header)),
+        11 => Box::new(ColorDreams::new(header)),
+        34 => Box::new(Bnrom::new(header)),
+        66 => Box::new(Gxrom::new(header)),
+        _ => return None,
+    };
+    Some(mapper)
+}
+
+/// The board of an empty cartridge slot.
+///
+/// Nothing responds on the CPU side. The pattern tables are wired straight
+/// to CHR, which allows to feed graphics into the PPU without a real board.
+#[derive(Debug, Default)]
+pub struct Empty;
+
+impl Mapper for Empty {
+    fn map_prg_read(&self, addr: u16) -> Mapped {
+        Mapped::None
+    }
+
+    fn map_prg_write(&mut self, addr: u16, val: u8) -> Mapped {
+        Mapped::None
+    }
+
+    fn map_chr(&self, addr: u16) -> Mapped {
+        Mapped::Chr(addr as usize)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        Mirroring::Horizontal
+    }
+}
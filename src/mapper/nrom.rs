@@ -0,0 +1,47 @@
+use super::{Mapped, Mapper};
+use crate::cartridge::{CartridgeHeader, Mirroring};
+
+/// NROM-128 and NROM-256 (mapper 0).
+///
+/// There is no bank switching, a single 16 KiB PRG bank is
+/// mirrored into `$C000-$FFFF`.
+#[derive(Debug)]
+pub struct Nrom {
+    prg_mask: usize,
+    mirroring: Mirroring,
+}
+
+impl Nrom {
+    pub fn new(header: &CartridgeHeader) -> Self {
+        let prg_size = header.prg_rom_bytes().max(0x4000);
+        Self {
+            prg_mask: prg_size - 1,
+            mirroring: header.mirroring(),
+        }
+    }
+}
+
+impl Mapper for Nrom {
+    fn map_prg_read(&self, addr: u16) -> Mapped {
+        match addr {
+            0x6000..=0x7FFF => Mapped::PrgRam(addr as usize & 0x1FFF),
+            0x8000..=0xFFFF => Mapped::PrgRom(addr as usize & self.prg_mask),
+            _ => Mapped::None,
+        }
+    }
+
+    fn map_prg_write(&mut self, addr: u16, val: u8) -> Mapped {
+        match addr {
+            0x6000..=0x7FFF => Mapped::PrgRam(addr as usize & 0x1FFF),
+            _ => Mapped::None,
+        }
+    }
+
+    fn map_chr(&self, addr: u16) -> Mapped {
+        Mapped::Chr(addr as usize)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+}
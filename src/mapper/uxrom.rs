@@ -0,0 +1,53 @@
+use super::{Mapped, Mapper};
+use crate::cartridge::{CartridgeHeader, Mirroring};
+
+/// The UxROM boards (mapper 2).
+///
+/// A switchable 16 KiB PRG bank at `$8000` and the last bank fixed at `$C000`.
+#[derive(Debug)]
+pub struct Uxrom {
+    prg_bank: u8,
+    /// The number of 16 KiB PRG banks.
+    prg_banks: usize,
+    mirroring: Mirroring,
+}
+
+impl Uxrom {
+    pub fn new(header: &CartridgeHeader) -> Self {
+        Self {
+            prg_bank: 0,
+            prg_banks: (header.prg_rom_bytes() / 0x4000).max(1),
+            mirroring: header.mirroring(),
+        }
+    }
+}
+
+impl Mapper for Uxrom {
+    fn map_prg_read(&self, addr: u16) -> Mapped {
+        let bank = match addr {
+            0x8000..=0xBFFF => self.prg_bank as usize % self.prg_banks,
+            0xC000..=0xFFFF => self.prg_banks - 1,
+            _ => return Mapped::None,
+        };
+        Mapped::PrgRom(bank * 0x4000 + (addr as usize & 0x3FFF))
+    }
+
+    fn map_prg_write(&mut self, addr: u16, val: u8) -> Mapped {
+        if addr >= 0x8000 {
+            self.prg_bank = val;
+        }
+        Mapped::None
+    }
+
+    fn map_chr(&self, addr: u16) -> Mapped {
+        Mapped::Chr(addr as usize)
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn bus_conflicts(&self) -> bool {
+        true
+    }
+}
@@ -1,19 +1,69 @@
 const CPU_RAM_SIZE: usize = 0x800;
+const ADDRESS_SPACE_SIZE: usize = 0x10000;
 
 /// The Memory trait represents a thing that has a memory to write and read data.
 pub trait Memory {
-    fn read(&self, addr: u16) -> u8;
+    /// A real read access, with all of its side effects like clearing
+    /// the vblank flag of PPUSTATUS.
+    fn read(&mut self, addr: u16) -> u8;
     fn write(&mut self, addr: u16, val: u8);
 
-    fn read_word(&self, addr: u16) -> u16 {
+    /// Returns what [`read`](Memory::read) would return right now, without
+    /// any side effects. Meant for debuggers and tracing.
+    fn peek(&self, addr: u16) -> u8;
+
+    /// Reads a little endian word, wrapping around at `$FFFF`.
+    fn read_word(&mut self, addr: u16) -> u16 {
         let lower = self.read(addr) as u16;
-        let upper = self.read(addr + 1) as u16;
+        let upper = self.read(addr.wrapping_add(1)) as u16;
+        upper << 8 | lower
+    }
+
+    /// Reads a word from the zeropage, wrapping around at `$FF` like the
+    /// pointers of the indirect addressing modes.
+    fn read_word_zero_page(&mut self, addr: u8) -> u16 {
+        let lower = self.read(addr as u16) as u16;
+        let upper = self.read(addr.wrapping_add(1) as u16) as u16;
+        upper << 8 | lower
+    }
+
+    /// Reads a word whose upper byte comes from the same page, like the
+    /// pointer of an indirect `JMP`, so `$10FF` reads `$10FF` and `$1000`.
+    fn read_word_page_wrap(&mut self, addr: u16) -> u16 {
+        let lower = self.read(addr) as u16;
+        let upper = self.read((addr & 0xFF00) | (addr.wrapping_add(1) & 0x00FF)) as u16;
+        upper << 8 | lower
+    }
+
+    /// Like [`read_word`](Memory::read_word), without side effects.
+    fn peek_word(&self, addr: u16) -> u16 {
+        let lower = self.peek(addr) as u16;
+        let upper = self.peek(addr.wrapping_add(1)) as u16;
         upper << 8 | lower
     }
 
+    /// Writes a little endian word, wrapping around at `$FFFF`.
     fn write_word(&mut self, addr: u16, val: u16) {
         self.write(addr, val as u8);
-        self.write(addr, (val >> 8) as u8);
+        self.write(addr.wrapping_add(1), (val >> 8) as u8);
+    }
+}
+
+/// A [`Memory`] that devices can read from by DMA, halting the CPU.
+/// The defaults describe a bus without any DMA.
+pub trait DmaBus: Memory {
+    /// The address a device wants to read by DMA. The CPU is halted on its
+    /// next read cycle to make room for it.
+    fn dma_request(&self) -> Option<u16> {
+        None
+    }
+
+    /// Delivers the byte the CPU read for [`dma_request`](DmaBus::dma_request).
+    fn dma_complete(&mut self, val: u8) {}
+
+    /// The number of cycles the APU has run, which DMA has to line up with.
+    fn apu_cycle(&self) -> u64 {
+        0
     }
 }
 
@@ -31,7 +81,11 @@ impl Default for Ram {
 }
 
 impl Memory for Ram {
-    fn read(&self, addr: u16) -> u8 {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.peek(addr)
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
         self.ram[(addr & 0x7FF) as usize]
     }
 
@@ -39,3 +93,33 @@ impl Memory for Ram {
         self.ram[(addr & 0x7FF) as usize] = val;
     }
 }
+
+/// A flat RAM that covers the whole 64K address space, handy to run the
+/// CPU without the rest of the console.
+pub struct FlatRam {
+    ram: Box<[u8; ADDRESS_SPACE_SIZE]>,
+}
+
+impl Default for FlatRam {
+    fn default() -> Self {
+        Self {
+            ram: Box::new([0u8; ADDRESS_SPACE_SIZE]),
+        }
+    }
+}
+
+impl Memory for FlatRam {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.peek(addr)
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        self.ram[addr as usize]
+    }
+
+    fn write(&mut self, addr: u16, val: u8) {
+        self.ram[addr as usize] = val;
+    }
+}
+
+impl DmaBus for FlatRam {}
@@ -0,0 +1,221 @@
+//! Turns the output of the APU channels into samples for the host.
+
+use crate::apu::{Apu, Channel};
+use crate::blip::BlipBuffer;
+use std::f64::consts::PI;
+
+/// The sample rate that is used until the frontend asks for another one.
+pub const DEFAULT_SAMPLE_RATE: u32 = 44100;
+
+/// The samples that are kept when nobody drains them, in seconds.
+const MAX_BUFFERED: u32 = 1;
+
+/// A first order filter of the analog output stage.
+#[derive(Debug)]
+enum Filter {
+    HighPass {
+        alpha: f32,
+        prev_in: f32,
+        prev_out: f32,
+    },
+    LowPass {
+        alpha: f32,
+        prev_out: f32,
+    },
+}
+
+impl Filter {
+    fn high_pass(cutoff: f64, sample_rate: f64) -> Self {
+        let rc = 1.0 / (2.0 * PI * cutoff);
+        let dt = 1.0 / sample_rate;
+        Filter::HighPass {
+            alpha: (rc / (rc + dt)) as f32,
+            prev_in: 0.0,
+            prev_out: 0.0,
+        }
+    }
+
+    fn low_pass(cutoff: f64, sample_rate: f64) -> Self {
+        let rc = 1.0 / (2.0 * PI * cutoff);
+        let dt = 1.0 / sample_rate;
+        Filter::LowPass {
+            alpha: (dt / (rc + dt)) as f32,
+            prev_out: 0.0,
+        }
+    }
+
+    fn process(&mut self, x: f32) -> f32 {
+        match self {
+            Filter::HighPass {
+                alpha,
+                prev_in,
+                prev_out,
+            } => {
+                *prev_out = *alpha * (*prev_out + x - *prev_in);
+                *prev_in = x;
+                *prev_out
+            }
+            Filter::LowPass { alpha, prev_out } => {
+                *prev_out += *alpha * (x - *prev_out);
+                *prev_out
+            }
+        }
+    }
+}
+
+/// The volume, mute and solo settings of the channels. They only change
+/// what is heard, the APU keeps running the same way.
+#[derive(Debug, Clone)]
+pub struct ChannelMix {
+    volume: [f32; 5],
+    muted: [bool; 5],
+    solo: [bool; 5],
+}
+
+impl Default for ChannelMix {
+    fn default() -> Self {
+        Self {
+            volume: [1.0; 5],
+            muted: [false; 5],
+            solo: [false; 5],
+        }
+    }
+}
+
+impl ChannelMix {
+    /// Scales the output of a channel, 1 is the volume of the real console.
+    pub fn set_volume(&mut self, channel: Channel, volume: f32) {
+        self.volume[channel as usize] = volume.max(0.0);
+    }
+
+    pub fn volume(&self, channel: Channel) -> f32 {
+        self.volume[channel as usize]
+    }
+
+    pub fn set_muted(&mut self, channel: Channel, muted: bool) {
+        self.muted[channel as usize] = muted;
+    }
+
+    pub fn is_muted(&self, channel: Channel) -> bool {
+        self.muted[channel as usize]
+    }
+
+    /// While any channel is soloed, only the soloed channels are heard.
+    pub fn set_solo(&mut self, channel: Channel, solo: bool) {
+        self.solo[channel as usize] = solo;
+    }
+
+    pub fn is_solo(&self, channel: Channel) -> bool {
+        self.solo[channel as usize]
+    }
+
+    /// The factor the output of a channel is scaled with.
+    pub fn gain(&self, channel: Channel) -> f32 {
+        let i = channel as usize;
+        let soloed = self.solo.iter().any(|s| *s);
+        if self.muted[i] || (soloed && !self.solo[i]) {
+            0.0
+        } else {
+            self.volume[i]
+        }
+    }
+}
+
+/// Mixes the channels with the non-linear DAC of the 2A03 and resamples
+/// the result to the sample rate of the host.
+#[derive(Debug)]
+pub struct Mixer {
+    sample_rate: u32,
+    pub channels: ChannelMix,
+    blip: BlipBuffer,
+    /// The output level during the last cycle.
+    level: f32,
+    /// The high-pass filters at 90 Hz and 440 Hz and the low-pass filter at 14 kHz.
+    filters: [Filter; 3],
+}
+
+impl Mixer {
+    /// Creates a mixer for an APU that is clocked at `clock_rate`.
+    pub fn new(clock_rate: f64, sample_rate: u32) -> Self {
+        let rate = sample_rate as f64;
+        Self {
+            sample_rate,
+            channels: ChannelMix::default(),
+            blip: BlipBuffer::new(clock_rate, rate),
+            level: 0.0,
+            filters: [
+                Filter::high_pass(90.0, rate),
+                Filter::high_pass(440.0, rate),
+                Filter::low_pass(14000.0, rate),
+            ],
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// The output of the DAC in the range 0-1.
+    pub fn mix(&self, apu: &Apu) -> f32 {
+        let output = |channel| apu.output(channel) as f32 * self.channels.gain(channel);
+        let pulse = output(Channel::Pulse1) + output(Channel::Pulse2);
+        let tnd =
+            3.0 * output(Channel::Triangle) + 2.0 * output(Channel::Noise) + output(Channel::Dmc);
+
+        // The approximations of the DAC from the nesdev wiki.
+        let mut level = 0.0;
+        if pulse > 0.0 {
+            level += 95.52 / (8128.0 / pulse + 100.0);
+        }
+        if tnd > 0.0 {
+            level += 163.67 / (24329.0 / tnd + 100.0);
+        }
+        level
+    }
+
+    /// Takes the output of the APU for a single CPU cycle.
+    pub fn clock(&mut self, apu: &Apu) {
+        let level = self.mix(apu);
+        if level != self.level {
+            self.blip.add_delta(level - self.level);
+            self.level = level;
+        }
+        self.blip.clock();
+
+        // Nobody is listening, so the oldest samples are dropped.
+        let max = (self.sample_rate * MAX_BUFFERED) as usize;
+        if self.blip.available() > max {
+            self.read(self.blip.available() - max, |_| {});
+        }
+    }
+
+    /// The number of samples that can be read.
+    pub fn available(&self) -> usize {
+        self.blip.available()
+    }
+
+    /// Fills `out` with as many samples in the range -1 to 1 as are available,
+    /// and returns how many that were.
+    pub fn read_samples(&mut self, out: &mut [f32]) -> usize {
+        let len = out.len();
+        let mut samples = out.iter_mut();
+        self.read(len, |s| *samples.next().unwrap() = s)
+    }
+
+    /// Like [`read_samples`](Mixer::read_samples), with 16 bit samples.
+    pub fn read_samples_i16(&mut self, out: &mut [i16]) -> usize {
+        let len = out.len();
+        let mut samples = out.iter_mut();
+        self.read(len, |s| {
+            *samples.next().unwrap() = (s * i16::MAX as f32) as i16;
+        })
+    }
+
+    fn read(&mut self, count: usize, mut f: impl FnMut(f32)) -> usize {
+        let filters = &mut self.filters;
+        self.blip.read(count, |s| {
+            let s = filters.iter_mut().fold(s, |s, filter| filter.process(s));
+            f(s.clamp(-1.0, 1.0));
+        })
+    }
+}
@@ -0,0 +1,247 @@
+use crate::apu::Channel;
+use crate::bus::Bus;
+use crate::cartridge::{Cartridge, Timing};
+use crate::cpu::{Cpu, Jam, Registers};
+use crate::mixer::{ChannelMix, Mixer, DEFAULT_SAMPLE_RATE};
+use crate::wav::WavWriter;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The number of frames between two flushes of the save file.
+const SAVE_INTERVAL: u64 = 60;
+
+/// The video standard of the console, which decides how many
+/// PPU dots are processed per CPU cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    /// 3 dots per CPU cycle.
+    Ntsc,
+    /// 3.2 dots per CPU cycle.
+    Pal,
+}
+
+impl Region {
+    /// The number of CPU cycles per second.
+    pub fn cpu_clock_rate(self) -> f64 {
+        match self {
+            Region::Ntsc => 1_789_773.0,
+            Region::Pal => 1_662_607.0,
+        }
+    }
+}
+
+/// The whole console, which drives all components from the master clock.
+pub struct Nes {
+    pub cpu: Cpu,
+    region: Region,
+    /// The fraction of a PPU dot that is left over for PAL, in fifths of a dot.
+    dot_remainder: u8,
+    mixer: Mixer,
+    save_error: Option<io::Error>,
+}
+
+impl Nes {
+    /// Creates a console for the region the cartridge header asks for.
+    pub fn new(cartridge: Cartridge) -> Self {
+        let region = match cartridge.header.timing() {
+            Timing::Pal => Region::Pal,
+            _ => Region::Ntsc,
+        };
+        Self::with_region(cartridge, region)
+    }
+
+    pub fn with_region(cartridge: Cartridge, region: Region) -> Self {
+        let mut bus = Bus::new(cartridge);
+        bus.ppu.set_region(region);
+        bus.apu.set_region(region);
+
+        Self {
+            cpu: Cpu::new(bus, Registers::default()),
+            region,
+            dot_remainder: 0,
+            mixer: Mixer::new(region.cpu_clock_rate(), DEFAULT_SAMPLE_RATE),
+            save_error: None,
+        }
+    }
+
+    pub fn region(&self) -> Region {
+        self.region
+    }
+
+    pub fn reset(&mut self) {
+        self.cpu.reset();
+    }
+
+    /// Advances the whole system by one CPU cycle.
+    pub fn step_cycle(&mut self) {
+        self.cpu.nmi_line = self.cpu.bus.ppu.nmi();
+        self.cpu.irq_line = self.cpu.bus.irq();
+        self.cpu.clock();
+        self.cpu.bus.clock_cartridge();
+        self.cpu.bus.clock_apu();
+        self.mixer.clock(&self.cpu.bus.apu);
+
+        let dots = match self.region {
+            Region::Ntsc => 3,
+            Region::Pal => {
+                self.dot_remainder += 16;
+                let dots = self.dot_remainder / 5;
+                self.dot_remainder %= 5;
+                dots
+            }
+        };
+        for _ in 0..dots {
+            self.cpu.bus.clock_ppu();
+        }
+    }
+
+    /// Runs the system until the CPU has finished the next instruction.
+    /// A jammed CPU only advances the rest of the system by one cycle.
+    pub fn step_instruction(&mut self) -> Result<(), Jam> {
+        loop {
+            self.step_cycle();
+            if self.cpu.at_instruction_boundary() {
+                break;
+            }
+        }
+        self.jammed()
+    }
+
+    /// Runs the system until the PPU has finished rendering the current frame.
+    /// The frame is finished even if the CPU jams, so it's up to the caller
+    /// to decide whether to keep going.
+    pub fn run_frame(&mut self) -> Result<(), Jam> {
+        let frame = self.cpu.bus.ppu.frame_count();
+        while self.cpu.bus.ppu.frame_count() == frame {
+            self.step_cycle();
+        }
+
+        // A failed write is retried on the next interval.
+        if self.cpu.bus.ppu.frame_count().is_multiple_of(SAVE_INTERVAL) {
+            self.save_error = self.flush_save().err();
+        }
+
+        self.jammed()
+    }
+
+    fn jammed(&self) -> Result<(), Jam> {
+        match self.cpu.halted {
+            Some(jam) => Err(jam),
+            None => Ok(()),
+        }
+    }
+
+    /// Writes the battery-backed RAM to the save file of the cartridge,
+    /// see [`Cartridge::attach_save_file`].
+    pub fn flush_save(&mut self) -> io::Result<()> {
+        self.cpu.bus.cartridge.flush_save()
+    }
+
+    /// The error of the last periodic flush of the save file in
+    /// [`run_frame`](Nes::run_frame), or `None` if it succeeded.
+    pub fn last_save_error(&self) -> Option<&io::Error> {
+        self.save_error.as_ref()
+    }
+
+    /// Changes the sample rate of the audio output, which drops all samples
+    /// that weren't drained yet.
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        let channels = self.mixer.channels.clone();
+        self.mixer = Mixer::new(self.region.cpu_clock_rate(), sample_rate);
+        self.mixer.channels = channels;
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.mixer.sample_rate()
+    }
+
+    /// The volume, mute and solo settings of the audio output.
+    pub fn channel_mix(&self) -> &ChannelMix {
+        &self.mixer.channels
+    }
+
+    pub fn channel_mix_mut(&mut self) -> &mut ChannelMix {
+        &mut self.mixer.channels
+    }
+
+    /// The number of audio samples that are ready to be drained.
+    pub fn audio_available(&self) -> usize {
+        self.mixer.available()
+    }
+
+    /// Moves as many mono audio samples into `out` as are available and
+    /// returns how many that were. At most one second of audio is kept.
+    pub fn drain_audio(&mut self, out: &mut [f32]) -> usize {
+        self.mixer.read_samples(out)
+    }
+
+    /// Like [`drain_audio`](Nes::drain_audio), with 16 bit samples.
+    pub fn drain_audio_i16(&mut self, out: &mut [i16]) -> usize {
+        self.mixer.read_samples_i16(out)
+    }
+
+    /// Runs the system for `frames` frames and writes the audio to a WAV file
+    /// at `path`. With `stems`, every APU channel is also written to a file of
+    /// its own, like `song-pulse1.wav` next to `song.wav`.
+    ///
+    /// The recording has its own mixers, so it doesn't take any samples from
+    /// [`drain_audio`](Nes::drain_audio). The mixed file uses the settings of
+    /// [`channel_mix`](Nes::channel_mix), the stems ignore them. The recording
+    /// goes on if the CPU jams, and is silent from then on.
+    pub fn record_wav(
+        &mut self,
+        path: impl AsRef<Path>,
+        frames: u64,
+        stems: bool,
+    ) -> io::Result<()> {
+        let path = path.as_ref();
+        let clock_rate = self.region.cpu_clock_rate();
+        let sample_rate = self.sample_rate();
+
+        let mut mixed = Mixer::new(clock_rate, sample_rate);
+        mixed.channels = self.mixer.channels.clone();
+        let mut tracks = vec![(mixed, WavWriter::create(path, sample_rate)?)];
+        if stems {
+            for &channel in Channel::ALL.iter() {
+                let mut stem = Mixer::new(clock_rate, sample_rate);
+                stem.channels.set_solo(channel, true);
+                tracks.push((
+                    stem,
+                    WavWriter::create(stem_path(path, channel), sample_rate)?,
+                ));
+            }
+        }
+
+        let mut buf = vec![0.0; sample_rate as usize];
+        for _ in 0..frames {
+            let frame = self.cpu.bus.ppu.frame_count();
+            while self.cpu.bus.ppu.frame_count() == frame {
+                self.step_cycle();
+                for (mixer, _) in tracks.iter_mut() {
+                    mixer.clock(&self.cpu.bus.apu);
+                }
+            }
+
+            for (mixer, wav) in tracks.iter_mut() {
+                let count = mixer.read_samples(&mut buf);
+                wav.write_samples(&buf[..count])?;
+            }
+        }
+
+        for (_, wav) in tracks {
+            wav.finish()?;
+        }
+        Ok(())
+    }
+
+    /// The last completed picture, see [`Ppu::frame`](crate::ppu::Ppu::frame).
+    pub fn frame(&self) -> &[u8] {
+        self.cpu.bus.ppu.frame()
+    }
+}
+
+/// The path of the stem of `channel` for a recording at `path`.
+fn stem_path(path: &Path, channel: Channel) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}-{}.wav", stem, channel.name()))
+}
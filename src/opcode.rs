@@ -20,7 +20,7 @@ const CYCLES: [u8; 256] = [
     /* 0xB0 */ 2, 5, 2, 5, 4, 4, 4, 4, 2, 4, 2, 4, 4, 4, 4, 4,
     /* 0xC0 */ 2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6,
     /* 0xD0 */ 2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
-    /* 0xE0 */ 2, 6, 3, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6,
+    /* 0xE0 */ 2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6,
     /* 0xF0 */ 2, 5, 2, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
 ];
 
@@ -28,275 +28,275 @@ pub const OPCODES: [Opcode; 256] = [
     // ==========================
     opcode!(BRK, Implied, 0),
     opcode!(ORA, IndirectXIndexed, 0x01),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(JAM, Implied, 0x02),
+    opcode!(SLO, IndirectXIndexed, 0x03),
     opcode!(NOP, Zeropage, 0x04),
     opcode!(ORA, Zeropage, 0x05),
     opcode!(ASL, Zeropage, 0x06),
-    Opcode::invalid(),
+    opcode!(SLO, Zeropage, 0x07),
     opcode!(PHP, Implied, 0x08),
     opcode!(ORA, Immediate, 0x09),
     opcode!(ASL, Accumulator, 0x0A),
-    Opcode::invalid(),
+    opcode!(ANC, Immediate, 0x0B),
     opcode!(NOP, Absolute, 0x0C),
     opcode!(ORA, Absolute, 0x0D),
     opcode!(ASL, Absolute, 0x0E),
-    Opcode::invalid(),
+    opcode!(SLO, Absolute, 0x0F),
     // ==========================
     opcode!(BPL, Relative, 0x10),
     opcode!(ORA, IndirectYIndexed, 0x11),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(JAM, Implied, 0x12),
+    opcode!(SLO, IndirectYIndexed, 0x13),
     opcode!(NOP, ZeropageXIndexed, 0x14),
     opcode!(ORA, ZeropageXIndexed, 0x15),
     opcode!(ASL, ZeropageXIndexed, 0x16),
-    Opcode::invalid(),
+    opcode!(SLO, ZeropageXIndexed, 0x17),
     opcode!(CLC, Implied, 0x18),
     opcode!(ORA, AbsoluteYIndexed, 0x19),
     opcode!(NOP, Implied, 0x1A),
-    Opcode::invalid(),
+    opcode!(SLO, AbsoluteYIndexed, 0x1B),
     opcode!(NOP, AbsoluteXIndexed, 0x1C),
     opcode!(ORA, AbsoluteXIndexed, 0x1D),
     opcode!(ASL, AbsoluteXIndexed, 0x1E),
-    Opcode::invalid(),
+    opcode!(SLO, AbsoluteXIndexed, 0x1F),
     // ==========================
     opcode!(JSR, Absolute, 0x20),
-    opcode!(AND, IndirectXIndexed, 0x20),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(AND, IndirectXIndexed, 0x21),
+    opcode!(JAM, Implied, 0x22),
+    opcode!(RLA, IndirectXIndexed, 0x23),
     opcode!(BIT, Zeropage, 0x24),
     opcode!(AND, Zeropage, 0x25),
     opcode!(ROL, Zeropage, 0x26),
-    Opcode::invalid(),
+    opcode!(RLA, Zeropage, 0x27),
     opcode!(PLP, Implied, 0x28),
     opcode!(AND, Immediate, 0x29),
     opcode!(ROL, Accumulator, 0x2A),
-    Opcode::invalid(),
+    opcode!(ANC, Immediate, 0x2B),
     opcode!(BIT, Absolute, 0x2C),
     opcode!(AND, Absolute, 0x2D),
     opcode!(ROL, Absolute, 0x2E),
-    Opcode::invalid(),
+    opcode!(RLA, Absolute, 0x2F),
     // ==========================
     opcode!(BMI, Relative, 0x30),
     opcode!(AND, IndirectYIndexed, 0x31),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(JAM, Implied, 0x32),
+    opcode!(RLA, IndirectYIndexed, 0x33),
     opcode!(NOP, ZeropageXIndexed, 0x34),
     opcode!(AND, ZeropageXIndexed, 0x35),
     opcode!(ROL, ZeropageXIndexed, 0x36),
-    Opcode::invalid(),
+    opcode!(RLA, ZeropageXIndexed, 0x37),
     opcode!(SEC, Implied, 0x38),
     opcode!(AND, AbsoluteYIndexed, 0x39),
     opcode!(NOP, Implied, 0x3A),
-    Opcode::invalid(),
+    opcode!(RLA, AbsoluteYIndexed, 0x3B),
     opcode!(NOP, AbsoluteXIndexed, 0x3C),
     opcode!(AND, AbsoluteXIndexed, 0x3D),
     opcode!(ROL, AbsoluteXIndexed, 0x3E),
-    Opcode::invalid(),
+    opcode!(RLA, AbsoluteXIndexed, 0x3F),
     // ==========================
     opcode!(RTI, Implied, 0x40),
     opcode!(EOR, IndirectXIndexed, 0x41),
-    Opcode::invalid(),
-    Opcode::invalid(),
-    opcode!(NOP, Zeropage, 0x04),
+    opcode!(JAM, Implied, 0x42),
+    opcode!(SRE, IndirectXIndexed, 0x43),
+    opcode!(NOP, Zeropage, 0x44),
     opcode!(EOR, Zeropage, 0x45),
     opcode!(LSR, Zeropage, 0x46),
-    Opcode::invalid(),
+    opcode!(SRE, Zeropage, 0x47),
     opcode!(PHA, Implied, 0x48),
     opcode!(EOR, Immediate, 0x49),
     opcode!(LSR, Accumulator, 0x4A),
-    Opcode::invalid(),
+    opcode!(ALR, Immediate, 0x4B),
     opcode!(JMP, Absolute, 0x4C),
     opcode!(EOR, Absolute, 0x4D),
     opcode!(LSR, Absolute, 0x4E),
-    Opcode::invalid(),
+    opcode!(SRE, Absolute, 0x4F),
     // ==========================
     opcode!(BVC, Relative, 0x50),
     opcode!(EOR, IndirectYIndexed, 0x51),
-    Opcode::invalid(),
-    Opcode::invalid(),
-    opcode!(NOP, ZeropageXIndexed, 0x34),
+    opcode!(JAM, Implied, 0x52),
+    opcode!(SRE, IndirectYIndexed, 0x53),
+    opcode!(NOP, ZeropageXIndexed, 0x54),
     opcode!(EOR, ZeropageXIndexed, 0x55),
     opcode!(LSR, ZeropageXIndexed, 0x56),
-    Opcode::invalid(),
+    opcode!(SRE, ZeropageXIndexed, 0x57),
     opcode!(CLI, Implied, 0x58),
     opcode!(EOR, AbsoluteYIndexed, 0x59),
     opcode!(NOP, Implied, 0x5A),
-    Opcode::invalid(),
+    opcode!(SRE, AbsoluteYIndexed, 0x5B),
     opcode!(NOP, AbsoluteXIndexed, 0x5C),
     opcode!(EOR, AbsoluteXIndexed, 0x5D),
     opcode!(LSR, AbsoluteXIndexed, 0x5E),
-    Opcode::invalid(),
+    opcode!(SRE, AbsoluteXIndexed, 0x5F),
     // ==========================
     opcode!(RTS, Implied, 0x60),
     opcode!(ADC, IndirectXIndexed, 0x61),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(JAM, Implied, 0x62),
+    opcode!(RRA, IndirectXIndexed, 0x63),
     opcode!(NOP, Zeropage, 0x64),
     opcode!(ADC, Zeropage, 0x65),
     opcode!(ROR, Zeropage, 0x66),
-    Opcode::invalid(),
+    opcode!(RRA, Zeropage, 0x67),
     opcode!(PLA, Implied, 0x68),
     opcode!(ADC, Immediate, 0x69),
     opcode!(ROR, Accumulator, 0x6A),
-    Opcode::invalid(),
+    opcode!(ARR, Immediate, 0x6B),
     opcode!(JMP, Indirect, 0x6C),
     opcode!(ADC, Absolute, 0x6D),
     opcode!(ROR, Absolute, 0x6E),
-    Opcode::invalid(),
+    opcode!(RRA, Absolute, 0x6F),
     // ==========================
     opcode!(BVS, Relative, 0x70),
     opcode!(ADC, IndirectYIndexed, 0x71),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(JAM, Implied, 0x72),
+    opcode!(RRA, IndirectYIndexed, 0x73),
     opcode!(NOP, ZeropageXIndexed, 0x74),
     opcode!(ADC, ZeropageXIndexed, 0x75),
     opcode!(ROR, ZeropageXIndexed, 0x76),
-    Opcode::invalid(),
+    opcode!(RRA, ZeropageXIndexed, 0x77),
     opcode!(SEI, Implied, 0x78),
     opcode!(ADC, AbsoluteYIndexed, 0x79),
     opcode!(NOP, Implied, 0x7A),
-    Opcode::invalid(),
+    opcode!(RRA, AbsoluteYIndexed, 0x7B),
     opcode!(NOP, AbsoluteXIndexed, 0x7C),
     opcode!(ADC, AbsoluteXIndexed, 0x7D),
     opcode!(ROR, AbsoluteXIndexed, 0x7E),
-    Opcode::invalid(),
+    opcode!(RRA, AbsoluteXIndexed, 0x7F),
     // ==========================
     opcode!(NOP, Immediate, 0x80),
     opcode!(STA, IndirectXIndexed, 0x81),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(NOP, Immediate, 0x82),
+    opcode!(SAX, IndirectXIndexed, 0x83),
     opcode!(STY, Zeropage, 0x84),
     opcode!(STA, Zeropage, 0x85),
     opcode!(STX, Zeropage, 0x86),
-    Opcode::invalid(),
+    opcode!(SAX, Zeropage, 0x87),
     opcode!(DEY, Implied, 0x88),
-    Opcode::invalid(),
+    opcode!(NOP, Immediate, 0x89),
     opcode!(TXA, Implied, 0x8A),
-    Opcode::invalid(),
+    opcode!(XAA, Immediate, 0x8B),
     opcode!(STY, Absolute, 0x8C),
     opcode!(STA, Absolute, 0x8D),
     opcode!(STX, Absolute, 0x8E),
-    Opcode::invalid(),
+    opcode!(SAX, Absolute, 0x8F),
     // ==========================
     opcode!(BCC, Relative, 0x90),
     opcode!(STA, IndirectYIndexed, 0x91),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(JAM, Implied, 0x92),
+    opcode!(SHA, IndirectYIndexed, 0x93),
     opcode!(STY, ZeropageXIndexed, 0x94),
     opcode!(STA, ZeropageXIndexed, 0x95),
     opcode!(STX, ZeropageYIndexed, 0x96),
-    Opcode::invalid(),
+    opcode!(SAX, ZeropageYIndexed, 0x97),
     opcode!(TYA, Implied, 0x98),
     opcode!(STA, AbsoluteYIndexed, 0x99),
     opcode!(TXS, Implied, 0x9A),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(TAS, AbsoluteYIndexed, 0x9B),
+    opcode!(SHY, AbsoluteXIndexed, 0x9C),
     opcode!(STA, AbsoluteXIndexed, 0x9D),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(SHX, AbsoluteYIndexed, 0x9E),
+    opcode!(SHA, AbsoluteYIndexed, 0x9F),
     // ==========================
     opcode!(LDY, Immediate, 0xA0),
     opcode!(LDA, IndirectXIndexed, 0xA1),
     opcode!(LDX, Immediate, 0xA2),
-    Opcode::invalid(),
+    opcode!(LAX, IndirectXIndexed, 0xA3),
     opcode!(LDY, Zeropage, 0xA4),
     opcode!(LDA, Zeropage, 0xA5),
     opcode!(LDX, Zeropage, 0xA6),
-    Opcode::invalid(),
+    opcode!(LAX, Zeropage, 0xA7),
     opcode!(TAY, Implied, 0xA8),
     opcode!(LDA, Immediate, 0xA9),
     opcode!(TAX, Implied, 0xAA),
-    Opcode::invalid(),
+    opcode!(LXA, Immediate, 0xAB),
     opcode!(LDY, Absolute, 0xAC),
     opcode!(LDA, Absolute, 0xAD),
     opcode!(LDX, Absolute, 0xAE),
-    Opcode::invalid(),
+    opcode!(LAX, Absolute, 0xAF),
     // ==========================
     opcode!(BCS, Relative, 0xB0),
     opcode!(LDA, IndirectYIndexed, 0xB1),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(JAM, Implied, 0xB2),
+    opcode!(LAX, IndirectYIndexed, 0xB3),
     opcode!(LDY, ZeropageXIndexed, 0xB4),
     opcode!(LDA, ZeropageXIndexed, 0xB5),
     opcode!(LDX, ZeropageYIndexed, 0xB6),
-    Opcode::invalid(),
+    opcode!(LAX, ZeropageYIndexed, 0xB7),
     opcode!(CLV, Implied, 0xB8),
     opcode!(LDA, AbsoluteYIndexed, 0xB9),
     opcode!(TSX, Implied, 0xBA),
-    Opcode::invalid(),
+    opcode!(LAS, AbsoluteYIndexed, 0xBB),
     opcode!(LDY, AbsoluteXIndexed, 0xBC),
     opcode!(LDA, AbsoluteXIndexed, 0xBD),
     opcode!(LDX, AbsoluteYIndexed, 0xBE),
-    Opcode::invalid(),
+    opcode!(LAX, AbsoluteYIndexed, 0xBF),
     // ==========================
     opcode!(CPY, Immediate, 0xC0),
     opcode!(CMP, IndirectXIndexed, 0xC1),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(NOP, Immediate, 0xC2),
+    opcode!(DCP, IndirectXIndexed, 0xC3),
     opcode!(CPY, Zeropage, 0xC4),
     opcode!(CMP, Zeropage, 0xC5),
     opcode!(DEC, Zeropage, 0xC6),
-    Opcode::invalid(),
+    opcode!(DCP, Zeropage, 0xC7),
     opcode!(INY, Implied, 0xC8),
     opcode!(CMP, Immediate, 0xC9),
     opcode!(DEX, Implied, 0xCA),
-    Opcode::invalid(),
+    opcode!(AXS, Immediate, 0xCB),
     opcode!(CPY, Absolute, 0xCC),
     opcode!(CMP, Absolute, 0xCD),
     opcode!(DEC, Absolute, 0xCE),
-    Opcode::invalid(),
+    opcode!(DCP, Absolute, 0xCF),
     // ==========================
     opcode!(BNE, Relative, 0xD0),
     opcode!(CMP, IndirectYIndexed, 0xD1),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(JAM, Implied, 0xD2),
+    opcode!(DCP, IndirectYIndexed, 0xD3),
     opcode!(NOP, ZeropageXIndexed, 0xD4),
     opcode!(CMP, ZeropageXIndexed, 0xD5),
     opcode!(DEC, ZeropageXIndexed, 0xD6),
-    Opcode::invalid(),
+    opcode!(DCP, ZeropageXIndexed, 0xD7),
     opcode!(CLD, Implied, 0xD8),
     opcode!(CMP, AbsoluteYIndexed, 0xD9),
     opcode!(NOP, Implied, 0xDA),
-    Opcode::invalid(),
+    opcode!(DCP, AbsoluteYIndexed, 0xDB),
     opcode!(NOP, AbsoluteXIndexed, 0xDC),
     opcode!(CMP, AbsoluteXIndexed, 0xDD),
     opcode!(DEC, AbsoluteXIndexed, 0xDE),
-    Opcode::invalid(),
+    opcode!(DCP, AbsoluteXIndexed, 0xDF),
     // ==========================
     opcode!(CPX, Immediate, 0xE0),
     opcode!(SBC, IndirectXIndexed, 0xE1),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(NOP, Immediate, 0xE2),
+    opcode!(ISC, IndirectXIndexed, 0xE3),
     opcode!(CPX, Zeropage, 0xE4),
     opcode!(SBC, Zeropage, 0xE5),
     opcode!(INC, Zeropage, 0xE6),
-    Opcode::invalid(),
+    opcode!(ISC, Zeropage, 0xE7),
     opcode!(INX, Implied, 0xE8),
     opcode!(SBC, Immediate, 0xE9),
     opcode!(NOP, Implied, 0xEA),
-    Opcode::invalid(),
+    opcode!(SBC, Immediate, 0xEB),
     opcode!(CPX, Absolute, 0xEC),
     opcode!(SBC, Absolute, 0xED),
     opcode!(INC, Absolute, 0xEE),
-    Opcode::invalid(),
+    opcode!(ISC, Absolute, 0xEF),
     // ==========================
     opcode!(BEQ, Relative, 0xF0),
     opcode!(SBC, IndirectYIndexed, 0xF1),
-    Opcode::invalid(),
-    Opcode::invalid(),
+    opcode!(JAM, Implied, 0xF2),
+    opcode!(ISC, IndirectYIndexed, 0xF3),
     opcode!(NOP, ZeropageXIndexed, 0xF4),
     opcode!(SBC, ZeropageXIndexed, 0xF5),
     opcode!(INC, ZeropageXIndexed, 0xF6),
-    Opcode::invalid(),
+    opcode!(ISC, ZeropageXIndexed, 0xF7),
     opcode!(SED, Implied, 0xF8),
     opcode!(SBC, AbsoluteYIndexed, 0xF9),
     opcode!(NOP, Implied, 0xFA),
-    Opcode::invalid(),
+    opcode!(ISC, AbsoluteYIndexed, 0xFB),
     opcode!(NOP, AbsoluteXIndexed, 0xFC),
     opcode!(SBC, AbsoluteXIndexed, 0xFD),
     opcode!(INC, AbsoluteXIndexed, 0xFE),
-    Opcode::invalid(),
+    opcode!(ISC, AbsoluteXIndexed, 0xFF),
     // ==========================
 ];
 
@@ -311,10 +311,6 @@ impl Opcode {
     pub const fn new(inst: Instruction, addr: AddressMode, cycles: u8) -> Self {
         Self { inst, addr, cycles }
     }
-
-    pub const fn invalid() -> Self {
-        Self::new(Instruction::XXX, AddressMode::Implied, 0)
-    }
 }
 
 #[derive(Debug)]
@@ -376,7 +372,31 @@ pub enum Instruction {
     TXS,
     TYA,
 
-    XXX,
+    // Unofficial instructions
+    ALR,
+    ANC,
+    ARR,
+    AXS,
+    DCP,
+    ISC,
+    LAX,
+    RLA,
+    RRA,
+    SAX,
+    SLO,
+    SRE,
+
+    // Unstable instructions, which depend on analog effects inside the chip
+    LAS,
+    LXA,
+    SHA,
+    SHX,
+    SHY,
+    TAS,
+    XAA,
+
+    /// Locks up the CPU until the next reset.
+    JAM,
 }
 
 #[derive(Debug)]
@@ -0,0 +1,786 @@
+use crate::cartridge::Cartridge;
+use crate::nes::Region;
+
+const VRAM_SIZE: usize = 0x800;
+const PALETTE_SIZE: usize = 0x20;
+const OAM_SIZE: usize = 0x100;
+const SECONDARY_OAM_SIZE: usize = 0x20;
+
+/// The width of the visible picture in pixels.
+pub const SCREEN_WIDTH: usize = 256;
+/// The height of the visible picture in pixels.
+pub const SCREEN_HEIGHT: usize = 240;
+
+const DOTS_PER_SCANLINE: u16 = 341;
+const VBLANK_SCANLINE: u16 = 241;
+/// The number of frames a bit of the I/O latch holds its value without
+/// being refreshed, which is roughly 600ms.
+const LATCH_DECAY_FRAMES: u64 = 36;
+
+#[derive(Debug)]
+#[repr(u8)]
+pub enum ControlFlag {
+    NametableX = 1 << 0,
+    NametableY = 1 << 1,
+    Increment = 1 << 2,
+    SpritePattern = 1 << 3,
+    BackgroundPattern = 1 << 4,
+    SpriteSize = 1 << 5,
+    MasterSlave = 1 << 6,
+    Nmi = 1 << 7,
+}
+
+#[derive(Debug)]
+#[repr(u8)]
+pub enum MaskFlag {
+    Greyscale = 1 << 0,
+    ShowBackgroundLeft = 1 << 1,
+    ShowSpritesLeft = 1 << 2,
+    ShowBackground = 1 << 3,
+    ShowSprites = 1 << 4,
+    EmphasizeRed = 1 << 5,
+    EmphasizeGreen = 1 << 6,
+    EmphasizeBlue = 1 << 7,
+}
+
+#[derive(Debug)]
+#[repr(u8)]
+pub enum StatusFlag {
+    SpriteOverflow = 1 << 5,
+    SpriteZeroHit = 1 << 6,
+    VerticalBlank = 1 << 7,
+}
+
+/// The CPU visible registers of the PPU.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Registers {
+    pub ctrl: u8,
+    pub mask: u8,
+    pub status: u8,
+    pub oam_addr: u8,
+}
+
+impl Registers {
+    pub fn ctrl_flag(&self, flag: ControlFlag) -> bool {
+        (self.ctrl & flag as u8) != 0
+    }
+
+    pub fn mask_flag(&self, flag: MaskFlag) -> bool {
+        (self.mask & flag as u8) != 0
+    }
+
+    pub fn set_status(&mut self, flag: StatusFlag, mode: bool) {
+        if mode {
+            self.status |= flag as u8;
+        } else {
+            self.status &= !(flag as u8);
+        }
+    }
+
+    pub fn get_status(&self, flag: StatusFlag) -> bool {
+        (self.status & flag as u8) != 0
+    }
+}
+
+/// The 2C02 picture processing unit.
+pub struct Ppu {
+    pub reg: Registers,
+    vram: [u8; VRAM_SIZE],
+    palette: [u8; PALETTE_SIZE],
+    oam: [u8; OAM_SIZE],
+    /// The current VRAM address (`v`).
+    vram_addr: u16,
+    /// The temporary VRAM address (`t`), which is shared by `PPUSCROLL` and `PPUADDR`.
+    temp_addr: u16,
+    /// The fine x scroll (`x`).
+    fine_x: u8,
+    /// The write toggle (`w`) shared by `PPUSCROLL` and `PPUADDR`.
+    write_toggle: bool,
+    /// Holds the value of the last `PPUDATA` read.
+    read_buffer: u8,
+    /// The I/O latch, which holds the last value driven onto the data bus
+    /// between the CPU and the PPU.
+    latch: u8,
+    /// The frame in which each bit of the latch was last driven. Bits that
+    /// aren't refreshed decay to 0.
+    latch_refresh: [u64; 8],
+
+    region: Region,
+    scanline: u16,
+    dot: u16,
+    odd_frame: bool,
+    frame_count: u64,
+    frame: Vec<u8>,
+
+    background: Background,
+    sprites: Sprites,
+}
+
+/// The latches and shift registers of the background pipeline.
+#[derive(Default)]
+struct Background {
+    nametable: u8,
+    attribute: u8,
+    pattern_lo: u8,
+    pattern_hi: u8,
+    shift_pattern_lo: u16,
+    shift_pattern_hi: u16,
+    shift_attribute_lo: u16,
+    shift_attribute_hi: u16,
+}
+
+/// The state of the sprite evaluation and the eight sprite output units.
+struct Sprites {
+    secondary_oam: [u8; SECONDARY_OAM_SIZE],
+    /// The number of sprites found during evaluation for the next scanline.
+    count: usize,
+    /// Whether sprite 0 was copied into secondary OAM for the next scanline.
+    zero_next: bool,
+    /// Whether the first output unit holds sprite 0 on the current scanline.
+    zero_current: bool,
+    /// The number of sprites loaded into the output units.
+    active: usize,
+    pattern_lo: [u8; 8],
+    pattern_hi: [u8; 8],
+    attribute: [u8; 8],
+    x: [u8; 8],
+}
+
+impl Default for Sprites {
+    fn default() -> Self {
+        Self {
+            secondary_oam: [0xFF; SECONDARY_OAM_SIZE],
+            count: 0,
+            zero_next: false,
+            zero_current: false,
+            active: 0,
+            pattern_lo: [0; 8],
+            pattern_hi: [0; 8],
+            attribute: [0; 8],
+            x: [0; 8],
+        }
+    }
+}
+
+impl Default for Ppu {
+    fn default() -> Self {
+        Self {
+            reg: Registers::default(),
+            vram: [0u8; VRAM_SIZE],
+            palette: [0u8; PALETTE_SIZE],
+            oam: [0u8; OAM_SIZE],
+            vram_addr: 0,
+            temp_addr: 0,
+            fine_x: 0,
+            write_toggle: false,
+            read_buffer: 0,
+            latch: 0,
+            latch_refresh: [0; 8],
+            region: Region::Ntsc,
+            scanline: 0,
+            dot: 0,
+            odd_frame: false,
+            frame_count: 0,
+            frame: vec![0u8; SCREEN_WIDTH * SCREEN_HEIGHT],
+            background: Background::default(),
+            sprites: Sprites::default(),
+        }
+    }
+}
+
+impl Ppu {
+    /// Reads one of the eight registers, which are mirrored every 8 bytes
+    /// through `$2000-$3FFF`.
+    pub fn read_register(&mut self, addr: u16, cart: &mut Cartridge) -> u8 {
+        self.latch = self.decayed_latch();
+        match addr & 0x7 {
+            // PPUSTATUS, the lower bits are not driven and come from the latch.
+            0x2 => {
+                self.drive_latch(self.reg.status, 0xE0);
+                self.reg.set_status(StatusFlag::VerticalBlank, false);
+                self.write_toggle = false;
+            }
+            // OAMDATA
+            0x4 => self.drive_latch(self.oam[self.reg.oam_addr as usize], 0xFF),
+            // PPUDATA
+            0x7 => {
+                let addr = self.vram_addr & 0x3FFF;
+                if addr >= 0x3F00 {
+                    // Palette reads are not buffered, but the buffer is still
+                    // filled with the nametable byte "behind" the palette.
+                    self.read_buffer = self.read(addr - 0x1000, cart);
+                    let val = self.read(addr, cart);
+                    self.drive_latch(val, 0x3F);
+                } else {
+                    self.drive_latch(self.read_buffer, 0xFF);
+                    self.read_buffer = self.read(addr, cart);
+                }
+                self.increment_vram_addr(cart);
+            }
+            // All other registers are write only.
+            _ => {}
+        }
+        self.latch
+    }
+
+    /// Puts the bits in `mask` of `val` into the latch and refreshes them.
+    fn drive_latch(&mut self, val: u8, mask: u8) {
+        self.latch = (self.latch & !mask) | (val & mask);
+        for (i, refresh) in self.latch_refresh.iter_mut().enumerate() {
+            if mask & (1 << i) != 0 {
+                *refresh = self.frame_count;
+            }
+        }
+    }
+
+    /// The latch with all bits that weren't refreshed for too long cleared.
+    fn decayed_latch(&self) -> u8 {
+        let mut latch = self.latch;
+        for (i, refresh) in self.latch_refresh.iter().enumerate() {
+            if self.frame_count - refresh >= LATCH_DECAY_FRAMES {
+                latch &= !(1 << i);
+            }
+        }
+        latch
+    }
+
+    /// Returns what [`read_register`](Ppu::read_register) would return,
+    /// without touching the flags, the latch or the VRAM address.
+    pub fn peek_register(&self, addr: u16) -> u8 {
+        let latch = self.decayed_latch();
+        match addr & 0x7 {
+            0x2 => (self.reg.status & 0xE0) | (latch & 0x1F),
+            0x4 => self.oam[self.reg.oam_addr as usize],
+            0x7 => {
+                let addr = self.vram_addr & 0x3FFF;
+                if addr >= 0x3F00 {
+                    (self.palette[palette_index(addr)] & 0x3F) | (latch & 0xC0)
+                } else {
+                    self.read_buffer
+                }
+            }
+            _ => latch,
+        }
+    }
+
+    pub fn write_register(&mut self, addr: u16, val: u8, cart: &mut Cartridge) {
+        self.drive_latch(val, 0xFF);
+        match addr & 0x7 {
+            // PPUCTRL
+            0x0 => {
+                self.reg.ctrl = val;
+                self.temp_addr = (self.temp_addr & !0x0C00) | ((val as u16 & 0x03) << 10);
+            }
+            // PPUMASK
+            0x1 => self.reg.mask = val,
+            // PPUSTATUS
+            0x2 => {}
+            // OAMADDR
+            0x3 => self.reg.oam_addr = val,
+            // OAMDATA
+            0x4 => {
+                self.oam[self.reg.oam_addr as usize] = val;
+                self.reg.oam_addr = self.reg.oam_addr.wrapping_add(1);
+            }
+            // PPUSCROLL
+            0x5 => {
+                if !self.write_toggle {
+                    self.temp_addr = (self.temp_addr & !0x001F) | (val as u16 >> 3);
+                    self.fine_x = val & 0x07;
+                } else {
+                    self.temp_addr = (self.temp_addr & !0x73E0)
+                        | ((val as u16 & 0x07) << 12)
+                        | ((val as u16 & 0xF8) << 2);
+                }
+                self.write_toggle = !self.write_toggle;
+            }
+            // PPUADDR
+            0x6 => {
+                if !self.write_toggle {
+                    self.temp_addr = (self.temp_addr & 0x00FF) | ((val as u16 & 0x3F) << 8);
+                } else {
+                    self.temp_addr = (self.temp_addr & 0xFF00) | val as u16;
+                    self.vram_addr = self.temp_addr;
+                    cart.ppu_address(self.vram_addr & 0x3FFF);
+                }
+                self.write_toggle = !self.write_toggle;
+            }
+            // PPUDATA
+            0x7 => {
+                self.write(self.vram_addr & 0x3FFF, val, cart);
+                self.increment_vram_addr(cart);
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    /// The last completed picture as 256x240 indices into the system palette.
+    pub fn frame(&self) -> &[u8] {
+        &self.frame
+    }
+
+    /// The number of frames that have been rendered since power on.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Selects the video timing. PAL consoles have 50 more vblank scanlines
+    /// and never skip a dot on odd frames.
+    pub fn set_region(&mut self, region: Region) {
+        self.region = region;
+    }
+
+    /// The last scanline of a frame, which prepares the rendering of the next frame.
+    fn prerender_scanline(&self) -> u16 {
+        match self.region {
+            Region::Ntsc => 261,
+            Region::Pal => 311,
+        }
+    }
+
+    /// The scanline that is currently processed, where the pre-render line
+    /// is the last scanline of the frame.
+    pub fn scanline(&self) -> u16 {
+        self.scanline
+    }
+
+    /// The dot of the current scanline that is processed next.
+    pub fn dot(&self) -> u16 {
+        self.dot
+    }
+
+    /// The state of the NMI output, which is active as long as the vertical blank
+    /// flag is set and NMIs are enabled in `PPUCTRL`.
+    pub fn nmi(&self) -> bool {
+        self.reg.get_status(StatusFlag::VerticalBlank) && self.reg.ctrl_flag(ControlFlag::Nmi)
+    }
+
+    fn rendering_enabled(&self) -> bool {
+        self.reg.mask_flag(MaskFlag::ShowBackground) || self.reg.mask_flag(MaskFlag::ShowSprites)
+    }
+
+    fn is_rendering(&self) -> bool {
+        self.rendering_enabled()
+            && (self.scanline < SCREEN_HEIGHT as u16 || self.scanline == self.prerender_scanline())
+    }
+
+    /// Advances the PPU by a single dot.
+    pub fn clock(&mut self, cart: &mut Cartridge) {
+        let visible = self.scanline < SCREEN_HEIGHT as u16;
+        let prerender = self.scanline == self.prerender_scanline();
+
+        if prerender && self.dot == 1 {
+            self.reg.set_status(StatusFlag::VerticalBlank, false);
+            self.reg.set_status(StatusFlag::SpriteZeroHit, false);
+            self.reg.set_status(StatusFlag::SpriteOverflow, false);
+        }
+
+        if self.scanline == VBLANK_SCANLINE && self.dot == 1 {
+            self.reg.set_status(StatusFlag::VerticalBlank, true);
+        }
+
+        if (visible || prerender) && self.rendering_enabled() {
+            self.clock_background(cart, prerender);
+            self.clock_sprites(cart, visible);
+        }
+
+        if visible && (1..=256).contains(&self.dot) {
+            self.render_pixel();
+        }
+
+        self.advance_dot();
+    }
+
+    fn advance_dot(&mut self) {
+        // On odd frames the idle dot at the end of the pre-render line is skipped
+        // while rendering is enabled.
+        if self.region == Region::Ntsc
+            && self.scanline == self.prerender_scanline()
+            && self.dot == 339
+            && self.odd_frame
+            && self.rendering_enabled()
+        {
+            self.dot = 340;
+        }
+
+        self.dot += 1;
+        if self.dot == DOTS_PER_SCANLINE {
+            self.dot = 0;
+            self.scanline += 1;
+
+            if self.scanline == SCREEN_HEIGHT as u16 {
+                self.frame_count += 1;
+            }
+
+            if self.scanline > self.prerender_scanline() {
+                self.scanline = 0;
+                self.odd_frame = !self.odd_frame;
+            }
+        }
+    }
+
+    fn clock_background(&mut self, cart: &mut Cartridge, prerender: bool) {
+        let dot = self.dot;
+
+        if (2..=257).contains(&dot) || (322..=337).contains(&dot) {
+            self.shift_background();
+
+            match (dot - 1) % 8 {
+                0 => {
+                    self.load_background_shifters();
+                    self.background.nametable = self.read(0x2000 | (self.vram_addr & 0x0FFF), cart);
+                }
+                2 => {
+                    let v = self.vram_addr;
+                    let addr = 0x23C0 | (v & 0x0C00) | ((v >> 4) & 0x38) | ((v >> 2) & 0x07);
+                    let mut attribute = self.read(addr, cart);
+                    if v & 0x40 != 0 {
+                        attribute >>= 4;
+                    }
+                    if v & 0x02 != 0 {
+                        attribute >>= 2;
+                    }
+                    self.background.attribute = attribute & 0x03;
+                }
+                4 => self.background.pattern_lo = self.read(self.background_pattern_addr(), cart),
+                6 => {
+                    let addr = self.background_pattern_addr() + 8;
+                    self.background.pattern_hi = self.read(addr, cart);
+                }
+                7 => self.increment_x(),
+                _ => {}
+            }
+        }
+
+        match dot {
+            256 => self.increment_y(),
+            257 => {
+                self.load_background_shifters();
+                self.copy_x();
+            }
+            280..=304 if prerender => self.copy_y(),
+            // Unused nametable fetches at the end of the scanline.
+            337 | 339 => {
+                self.read(0x2000 | (self.vram_addr & 0x0FFF), cart);
+            }
+            _ => {}
+        }
+    }
+
+    fn background_pattern_addr(&self) -> u16 {
+        let table = if self.reg.ctrl_flag(ControlFlag::BackgroundPattern) {
+            0x1000
+        } else {
+            0x0000
+        };
+        let fine_y = (self.vram_addr >> 12) & 0x07;
+        table | ((self.background.nametable as u16) << 4) | fine_y
+    }
+
+    fn shift_background(&mut self) {
+        let bg = &mut self.background;
+        bg.shift_pattern_lo <<= 1;
+        bg.shift_pattern_hi <<= 1;
+        bg.shift_attribute_lo <<= 1;
+        bg.shift_attribute_hi <<= 1;
+    }
+
+    fn load_background_shifters(&mut self) {
+        let bg = &mut self.background;
+        bg.shift_pattern_lo = (bg.shift_pattern_lo & 0xFF00) | bg.pattern_lo as u16;
+        bg.shift_pattern_hi = (bg.shift_pattern_hi & 0xFF00) | bg.pattern_hi as u16;
+
+        let attribute = bg.attribute;
+        let expand = |bit: u8| if attribute & bit != 0 { 0xFF } else { 0x00 };
+        bg.shift_attribute_lo = (bg.shift_attribute_lo & 0xFF00) | expand(0x01);
+        bg.shift_attribute_hi = (bg.shift_attribute_hi & 0xFF00) | expand(0x02);
+    }
+
+    fn clock_sprites(&mut self, cart: &mut Cartridge, visible: bool) {
+        match self.dot {
+            257 => {
+                if visible {
+                    self.evaluate_sprites();
+                } else {
+                    // No sprites are evaluated on the pre-render line, so
+                    // scanline 0 never shows any sprites.
+                    self.sprites.secondary_oam = [0xFF; SECONDARY_OAM_SIZE];
+                    self.sprites.count = 0;
+                    self.sprites.zero_next = false;
+                }
+                self.sprites.active = self.sprites.count;
+                self.sprites.zero_current = self.sprites.zero_next;
+                self.reg.oam_addr = 0;
+            }
+            258..=320 => self.reg.oam_addr = 0,
+            _ => {}
+        }
+
+        if (257..=320).contains(&self.dot) {
+            let slot = (self.dot - 257) as usize / 8;
+            match (self.dot - 257) % 8 {
+                // Garbage nametable fetches.
+                0 | 2 => {
+                    self.read(0x2000 | (self.vram_addr & 0x0FFF), cart);
+                }
+                4 => {
+                    let addr = self.sprite_pattern_addr(slot);
+                    let pattern = self.read(addr, cart);
+                    self.load_sprite_pattern(slot, pattern, false);
+                }
+                6 => {
+                    let addr = self.sprite_pattern_addr(slot) + 8;
+                    let pattern = self.read(addr, cart);
+                    self.load_sprite_pattern(slot, pattern, true);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn sprite_height(&self) -> u16 {
+        if self.reg.ctrl_flag(ControlFlag::SpriteSize) {
+            16
+        } else {
+            8
+        }
+    }
+
+    /// Fills the secondary OAM with the sprites of the next scanline.
+    ///
+    /// This also emulates the broken overflow detection of the hardware,
+    /// which increments both the sprite and the byte index after eight
+    /// sprites were found.
+    fn evaluate_sprites(&mut self) {
+        let height = self.sprite_height();
+        let in_range = |y: u8| self.scanline.wrapping_sub(y as u16) < height;
+
+        let mut secondary = [0xFF; SECONDARY_OAM_SIZE];
+        let mut count = 0;
+        let mut zero = false;
+        let mut n = 0;
+
+        while n < 64 && count < 8 {
+            let entry = &self.oam[n * 4..n * 4 + 4];
+            if in_range(entry[0]) {
+                secondary[count * 4..count * 4 + 4].copy_from_slice(entry);
+                count += 1;
+                zero |= n == 0;
+            }
+            n += 1;
+        }
+
+        let mut m = 0;
+        let mut overflow = false;
+        while n < 64 {
+            if in_range(self.oam[n * 4 + m]) {
+                overflow = true;
+                break;
+            }
+            n += 1;
+            m = (m + 1) & 0x03;
+        }
+
+        if overflow {
+            self.reg.set_status(StatusFlag::SpriteOverflow, true);
+        }
+        self.sprites.secondary_oam = secondary;
+        self.sprites.count = count;
+        self.sprites.zero_next = zero;
+    }
+
+    fn sprite_pattern_addr(&self, slot: usize) -> u16 {
+        let entry = &self.sprites.secondary_oam[slot * 4..slot * 4 + 4];
+        let (y, tile, attribute) = (entry[0], entry[1] as u16, entry[2]);
+
+        let height = self.sprite_height();
+        let mut row = self.scanline.wrapping_sub(y as u16) & (height - 1);
+        if attribute & 0x80 != 0 {
+            row = height - 1 - row;
+        }
+
+        if height == 16 {
+            let table = (tile & 0x01) * 0x1000;
+            let tile = (tile & 0xFE) + (row >> 3);
+            table | (tile << 4) | (row & 0x07)
+        } else {
+            let table = if self.reg.ctrl_flag(ControlFlag::SpritePattern) {
+                0x1000
+            } else {
+                0x0000
+            };
+            table | (tile << 4) | row
+        }
+    }
+
+    fn load_sprite_pattern(&mut self, slot: usize, pattern: u8, high: bool) {
+        let sprites = &mut self.sprites;
+        let attribute = sprites.secondary_oam[slot * 4 + 2];
+
+        let pattern = if slot >= sprites.count {
+            0
+        } else if attribute & 0x40 != 0 {
+            pattern.reverse_bits()
+        } else {
+            pattern
+        };
+
+        if high {
+            sprites.pattern_hi[slot] = pattern;
+        } else {
+            sprites.pattern_lo[slot] = pattern;
+        }
+        sprites.attribute[slot] = attribute;
+        sprites.x[slot] = sprites.secondary_oam[slot * 4 + 3];
+    }
+
+    fn render_pixel(&mut self) {
+        let x = (self.dot - 1) as usize;
+
+        let mut bg_pixel = 0;
+        let mut bg_palette = 0;
+        if self.reg.mask_flag(MaskFlag::ShowBackground)
+            && (x >= 8 || self.reg.mask_flag(MaskFlag::ShowBackgroundLeft))
+        {
+            let bg = &self.background;
+            let mux = 0x8000 >> self.fine_x;
+            let bit = |shifter: u16| (shifter & mux != 0) as u8;
+            bg_pixel = bit(bg.shift_pattern_hi) << 1 | bit(bg.shift_pattern_lo);
+            bg_palette = bit(bg.shift_attribute_hi) << 1 | bit(bg.shift_attribute_lo);
+        }
+
+        let mut sprite_pixel = 0;
+        let mut sprite_palette = 0;
+        let mut sprite_behind = false;
+        let mut sprite_zero = false;
+        let show_sprites = self.reg.mask_flag(MaskFlag::ShowSprites)
+            && (x >= 8 || self.reg.mask_flag(MaskFlag::ShowSpritesLeft));
+
+        let sprites = &mut self.sprites;
+        for i in 0..sprites.active {
+            if sprites.x[i] > 0 {
+                sprites.x[i] -= 1;
+                continue;
+            }
+
+            let pixel = (sprites.pattern_hi[i] >> 7) << 1 | (sprites.pattern_lo[i] >> 7);
+            sprites.pattern_hi[i] <<= 1;
+            sprites.pattern_lo[i] <<= 1;
+
+            if show_sprites && sprite_pixel == 0 && pixel != 0 {
+                sprite_pixel = pixel;
+                sprite_palette = (sprites.attribute[i] & 0x03) + 4;
+                sprite_behind = sprites.attribute[i] & 0x20 != 0;
+                sprite_zero = i == 0 && sprites.zero_current;
+            }
+        }
+
+        if sprite_zero && bg_pixel != 0 && sprite_pixel != 0 && x != 255 {
+            self.reg.set_status(StatusFlag::SpriteZeroHit, true);
+        }
+
+        let (pixel, palette) = match (bg_pixel, sprite_pixel) {
+            (0, 0) => (0, 0),
+            (0, _) => (sprite_pixel, sprite_palette),
+            (_, 0) => (bg_pixel, bg_palette),
+            _ if sprite_behind => (bg_pixel, bg_palette),
+            _ => (sprite_pixel, sprite_palette),
+        };
+
+        let mut color = self.palette[palette_index(((palette as u16) << 2) | pixel as u16)];
+        color &= if self.reg.mask_flag(MaskFlag::Greyscale) {
+            0x30
+        } else {
+            0x3F
+        };
+        self.frame[self.scanline as usize * SCREEN_WIDTH + x] = color;
+    }
+
+    /// Increments the coarse x scroll and switches the horizontal nametable on overflow.
+    fn increment_x(&mut self) {
+        if self.vram_addr & 0x001F == 31 {
+            self.vram_addr &= !0x001F;
+            self.vram_addr ^= 0x0400;
+        } else {
+            self.vram_addr += 1;
+        }
+    }
+
+    /// Increments the fine y scroll and carries into the coarse y scroll,
+    /// switching the vertical nametable after row 29.
+    fn increment_y(&mut self) {
+        if self.vram_addr & 0x7000 != 0x7000 {
+            self.vram_addr += 0x1000;
+            return;
+        }
+
+        self.vram_addr &= !0x7000;
+        let mut coarse_y = (self.vram_addr & 0x03E0) >> 5;
+        if coarse_y == 29 {
+            coarse_y = 0;
+            self.vram_addr ^= 0x0800;
+        } else if coarse_y == 31 {
+            coarse_y = 0;
+        } else {
+            coarse_y += 1;
+        }
+        self.vram_addr = (self.vram_addr & !0x03E0) | (coarse_y << 5);
+    }
+
+    fn copy_x(&mut self) {
+        self.vram_addr = (self.vram_addr & !0x041F) | (self.temp_addr & 0x041F);
+    }
+
+    fn copy_y(&mut self) {
+        self.vram_addr = (self.vram_addr & !0x7BE0) | (self.temp_addr & 0x7BE0);
+    }
+
+    fn increment_vram_addr(&mut self, cart: &mut Cartridge) {
+        // Accessing PPUDATA while rendering increments both scroll
+        // counters instead of the normal address increment.
+        if self.is_rendering() {
+            self.increment_x();
+            self.increment_y();
+            return;
+        }
+
+        let step = if self.reg.ctrl_flag(ControlFlag::Increment) {
+            32
+        } else {
+            1
+        };
+        self.vram_addr = self.vram_addr.wrapping_add(step) & 0x7FFF;
+
+        // Outside of rendering the address is visible on the PPU address bus.
+        cart.ppu_address(self.vram_addr & 0x3FFF);
+    }
+
+    /// Reads from the PPU address space.
+    fn read(&self, addr: u16, cart: &mut Cartridge) -> u8 {
+        match addr {
+            0x0000..=0x3EFF => cart.read_ppu(addr, &self.vram),
+            0x3F00..=0x3FFF => self.palette[palette_index(addr)],
+            _ => unreachable!(),
+        }
+    }
+
+    /// Writes into the PPU address space.
+    fn write(&mut self, addr: u16, val: u8, cart: &mut Cartridge) {
+        match addr {
+            0x0000..=0x3EFF => cart.write_ppu(addr, val, &mut self.vram),
+            0x3F00..=0x3FFF => self.palette[palette_index(addr)] = val,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// The background color entries `$3F10`, `$3F14`, `$3F18` and `$3F1C`
+/// are mirrors of `$3F00`, `$3F04`, `$3F08` and `$3F0C`.
+fn palette_index(addr: u16) -> usize {
+    let idx = addr as usize & 0x1F;
+    if idx & 0x13 == 0x10 {
+        idx & 0x0F
+    } else {
+        idx
+    }
+}
@@ -0,0 +1,66 @@
+//! Writes audio to 16 bit mono PCM WAV files.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// The size of the RIFF header, the format chunk and the header of the data chunk.
+const HEADER_SIZE: u32 = 44;
+
+/// Streams samples into a WAV file. The sizes in the header are filled in
+/// by [`finish`](WavWriter::finish).
+pub struct WavWriter<W: Write + Seek> {
+    inner: W,
+    samples: u32,
+}
+
+impl WavWriter<BufWriter<File>> {
+    pub fn create(path: impl AsRef<Path>, sample_rate: u32) -> io::Result<Self> {
+        Self::new(BufWriter::new(File::create(path)?), sample_rate)
+    }
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(mut inner: W, sample_rate: u32) -> io::Result<Self> {
+        inner.write_all(b"RIFF")?;
+        inner.write_all(&(HEADER_SIZE - 8).to_le_bytes())?;
+        inner.write_all(b"WAVE")?;
+
+        inner.write_all(b"fmt ")?;
+        inner.write_all(&16u32.to_le_bytes())?;
+        // PCM with a single channel.
+        inner.write_all(&1u16.to_le_bytes())?;
+        inner.write_all(&1u16.to_le_bytes())?;
+        inner.write_all(&sample_rate.to_le_bytes())?;
+        inner.write_all(&(sample_rate * 2).to_le_bytes())?;
+        inner.write_all(&2u16.to_le_bytes())?;
+        inner.write_all(&16u16.to_le_bytes())?;
+
+        inner.write_all(b"data")?;
+        inner.write_all(&0u32.to_le_bytes())?;
+        Ok(Self { inner, samples: 0 })
+    }
+
+    /// Appends samples in the range -1 to 1.
+    pub fn write_samples(&mut self, samples: &[f32]) -> io::Result<()> {
+        for s in samples {
+            let s = (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.inner.write_all(&s.to_le_bytes())?;
+        }
+        self.samples += samples.len() as u32;
+        Ok(())
+    }
+
+    /// Fills in the sizes of the header and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let data_size = self.samples * 2;
+        self.inner.seek(SeekFrom::Start(4))?;
+        self.inner
+            .write_all(&(HEADER_SIZE - 8 + data_size).to_le_bytes())?;
+        self.inner.seek(SeekFrom::Start(HEADER_SIZE as u64 - 4))?;
+        self.inner.write_all(&data_size.to_le_bytes())?;
+        self.inner.seek(SeekFrom::End(0))?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
@@ -0,0 +1,244 @@
+mod common;
+
+use common::idle_console_with;
+use nesmu::{
+    apu::Channel,
+    bus::Bus,
+    cartridge::Cartridge,
+    mem::Memory,
+    nes::{Nes, Region},
+};
+
+fn clock(bus: &mut Bus, cycles: u32) {
+    for _ in 0..cycles {
+        bus.clock_apu();
+    }
+}
+
+#[test]
+fn length_counter_status() {
+    let mut bus = Bus::default();
+
+    // A disabled channel ignores the length.
+    bus.write(0x4003, 0x08);
+    assert_eq!(bus.read(0x4015) & 0x0F, 0x00);
+
+    bus.write(0x4015, 0x0F);
+    bus.write(0x4003, 0x08);
+    bus.write(0x400B, 0x08);
+    bus.write(0x400F, 0x08);
+    assert_eq!(bus.read(0x4015) & 0x0F, 0x0D);
+
+    bus.write(0x4015, 0x01);
+    assert_eq!(bus.read(0x4015) & 0x0F, 0x01);
+}
+
+#[test]
+fn length_counter_runs_out() {
+    let mut bus = Bus::default();
+    bus.write(0x4017, 0x40);
+    bus.write(0x4015, 0x01);
+    // A length of 2, which runs out after the two half frames of a sequence.
+    bus.write(0x4003, 0x18);
+
+    clock(&mut bus, 14920);
+    assert_eq!(bus.read(0x4015) & 0x01, 0x01);
+    clock(&mut bus, 14920);
+    assert_eq!(bus.read(0x4015) & 0x01, 0x00);
+
+    // The halt flag keeps it running.
+    bus.write(0x4000, 0x20);
+    bus.write(0x4003, 0x18);
+    clock(&mut bus, 29830 * 2);
+    assert_eq!(bus.read(0x4015) & 0x01, 0x01);
+}
+
+#[test]
+fn frame_irq() {
+    let mut bus = Bus::default();
+
+    clock(&mut bus, 29820);
+    assert!(!bus.irq());
+    clock(&mut bus, 20);
+    assert!(bus.irq());
+
+    // Reading the status clears the flag.
+    assert_eq!(bus.read(0x4015) & 0x40, 0x40);
+    assert_eq!(bus.read(0x4015) & 0x40, 0x00);
+    assert!(!bus.irq());
+
+    // Setting the inhibit flag clears it as well.
+    clock(&mut bus, 29830);
+    assert!(bus.irq());
+    bus.write(0x4017, 0x40);
+    assert!(!bus.irq());
+}
+
+#[test]
+fn five_step_sequence_has_no_irq() {
+    let mut bus = Bus::default();
+    bus.write(0x4017, 0x80);
+    clock(&mut bus, 37282 * 2);
+    assert!(!bus.irq());
+}
+
+#[test]
+fn status_keeps_open_bus() {
+    let mut bus = Bus::default();
+    bus.write(0x0000, 0xFF);
+    bus.read(0x0000);
+    assert_eq!(bus.read(0x4015), 0x20);
+    assert_eq!(bus.open_bus(), 0xFF);
+}
+
+#[test]
+fn pulse_duty_cycle() {
+    let mut bus = Bus::default();
+    bus.write(0x4015, 0x01);
+    // 50% duty, constant volume 15 and a period of 16 APU cycles.
+    bus.write(0x4000, 0xBF);
+    bus.write(0x4002, 0x0F);
+    bus.write(0x4003, 0x08);
+
+    let outputs: Vec<u8> = (0..8)
+        .map(|_| {
+            clock(&mut bus, 32);
+            bus.apu.output(Channel::Pulse1)
+        })
+        .collect();
+    assert_eq!(outputs, [15, 15, 15, 15, 0, 0, 0, 0]);
+}
+
+#[test]
+fn sweep_mutes_pulse() {
+    let mut bus = Bus::default();
+    bus.write(0x4015, 0x03);
+    for base in [0x4000, 0x4004].iter() {
+        bus.write(*base, 0xBF);
+        bus.write(base + 2, 0x00);
+        bus.write(base + 3, 0x0E);
+    }
+    // The target period is out of range even though the sweep is disabled.
+    bus.write(0x4001, 0x01);
+    // A negated sweep never overflows.
+    bus.write(0x4005, 0x09);
+
+    let mut pulse1 = 0;
+    let mut pulse2 = 0;
+    for _ in 0..0x2000 {
+        bus.clock_apu();
+        pulse1 |= bus.apu.output(Channel::Pulse1);
+        pulse2 |= bus.apu.output(Channel::Pulse2);
+    }
+    assert_eq!(pulse1, 0);
+    assert_eq!(pulse2, 15);
+}
+
+#[test]
+fn triangle_needs_linear_counter() {
+    let mut bus = Bus::default();
+    bus.write(0x4015, 0x04);
+    bus.write(0x400A, 0x10);
+    bus.write(0x400B, 0x08);
+
+    // The linear counter is only loaded on the next quarter frame.
+    clock(&mut bus, 1000);
+    assert_eq!(bus.apu.output(Channel::Triangle), 15);
+
+    bus.write(0x4008, 0x7F);
+    bus.write(0x400B, 0x08);
+    clock(&mut bus, 7460);
+    let mut levels = Vec::new();
+    for _ in 0..32 {
+        clock(&mut bus, 17);
+        levels.push(bus.apu.output(Channel::Triangle));
+    }
+    levels.sort_unstable();
+    levels.dedup();
+    assert_eq!(levels.len(), 16);
+}
+
+#[test]
+fn noise_is_random() {
+    let mut bus = Bus::default();
+    bus.write(0x4015, 0x08);
+    bus.write(0x400C, 0x3F);
+    bus.write(0x400E, 0x00);
+    bus.write(0x400F, 0x08);
+
+    let mut high = 0;
+    for _ in 0..1000 {
+        clock(&mut bus, 4);
+        if bus.apu.output(Channel::Noise) == 15 {
+            high += 1;
+        }
+    }
+    assert!((400..600).contains(&high), "{}", high);
+}
+
+/// Creates an idle console with an NROM cartridge whose PRG-ROM is filled
+/// with `sample`.
+fn dmc_console(sample: u8) -> Nes {
+    let mut rom = vec![0x4E, 0x45, 0x53, 0x1A, 1, 1];
+    rom.resize(16, 0);
+    rom.extend(vec![sample; 0x4000]);
+    rom.extend(vec![0; 0x2000]);
+    let cart = Cartridge::load(&mut rom.as_slice()).expect("failed to load cartridge");
+    idle_console_with(cart, Region::Ntsc)
+}
+
+#[test]
+fn dmc_dma_stalls_cpu() {
+    let mut nes = dmc_console(0x00);
+    // The fastest rate and a single byte at $C000.
+    nes.cpu.bus.write(0x4010, 0x0F);
+    nes.cpu.bus.write(0x4012, 0x00);
+    nes.cpu.bus.write(0x4013, 0x00);
+    nes.cpu.bus.write(0x4015, 0x10);
+    assert_eq!(nes.cpu.bus.read(0x4015) & 0x10, 0x10);
+
+    for _ in 0..30 {
+        nes.step_instruction().unwrap();
+    }
+    let cycles = nes.cpu.cycle_count;
+    assert!((93..=94).contains(&cycles), "{}", cycles);
+    assert_eq!(nes.cpu.bus.read(0x4015) & 0x10, 0x00);
+    assert!(!nes.cpu.bus.irq());
+}
+
+#[test]
+fn dmc_irq_and_looping() {
+    let mut nes = dmc_console(0x00);
+    nes.cpu.bus.write(0x4010, 0x8F);
+    nes.cpu.bus.write(0x4015, 0x10);
+    for _ in 0..10 {
+        nes.step_instruction().unwrap();
+    }
+    assert!(nes.cpu.bus.irq());
+    assert_eq!(nes.cpu.bus.read(0x4015) & 0x80, 0x80);
+
+    // Writing $4015 acknowledges the IRQ, a looping sample never raises it.
+    nes.cpu.bus.write(0x4010, 0xCF);
+    nes.cpu.bus.write(0x4015, 0x10);
+    assert!(!nes.cpu.bus.irq());
+    for _ in 0..1000 {
+        nes.step_instruction().unwrap();
+    }
+    assert!(!nes.cpu.bus.irq());
+    assert_eq!(nes.cpu.bus.read(0x4015) & 0x10, 0x10);
+}
+
+#[test]
+fn dmc_output() {
+    let mut nes = dmc_console(0xFF);
+    nes.cpu.bus.write(0x4010, 0x4F);
+    nes.cpu.bus.write(0x4011, 0x40);
+    nes.cpu.bus.write(0x4015, 0x10);
+    assert_eq!(nes.cpu.bus.apu.output(Channel::Dmc), 0x40);
+
+    // Every set bit raises the level by 2 as long as it stays below 128.
+    for _ in 0..1000 {
+        nes.step_instruction().unwrap();
+    }
+    assert_eq!(nes.cpu.bus.apu.output(Channel::Dmc), 0x7E);
+}
@@ -0,0 +1,678 @@
+use nesmu::{
+    bus::Bus,
+    cartridge::{
+        Cartridge, CartridgeHeader, CartridgeLoadError, ConsoleType, ExpansionDevice, HeaderFormat,
+        Mirroring, Timing,
+    },
+    mem::Memory,
+};
+
+/// Builds an iNES image where every byte of a PRG bank holds the bank number.
+fn ines(mapper: u8, flags_6: u8, prg_chunks: u8, chr_chunks: u8) -> Vec<u8> {
+    let mut rom = vec![
+        b'N',
+        b'E',
+        b'S',
+        0x1A,
+        prg_chunks,
+        chr_chunks,
+        (mapper << 4) | flags_6,
+        mapper & 0xF0,
+    ];
+    rom.resize(16, 0);
+    for bank in 0..prg_chunks {
+        rom.extend_from_slice(&[bank; 0x4000]);
+    }
+    for bank in 0..chr_chunks {
+        rom.extend_from_slice(&[bank; 0x2000]);
+    }
+    rom
+}
+
+fn load(rom: Vec<u8>) -> Bus {
+    let cart = Cartridge::load(&mut rom.as_slice()).expect("failed to load cartridge");
+    Bus::new(cart)
+}
+
+fn read_vram(bus: &mut Bus, addr: u16) -> u8 {
+    bus.write(0x2006, (addr >> 8) as u8);
+    bus.write(0x2006, addr as u8);
+    bus.read(0x2007);
+    bus.read(0x2007)
+}
+
+fn header(bytes: &[u8]) -> CartridgeHeader {
+    let mut raw = [0u8; 16];
+    raw[..4].copy_from_slice(b"NES\x1a");
+    raw[4..4 + bytes.len()].copy_from_slice(bytes);
+    CartridgeHeader::from_bytes(&raw)
+}
+
+#[test]
+fn ines_header() {
+    let header = header(&[2, 1, 0x13, 0x40, 0, 0x01]);
+
+    assert_eq!(header.format(), HeaderFormat::Ines);
+    assert_eq!(header.mapper(), 0x41);
+    assert_eq!(header.submapper(), 0);
+    assert_eq!(header.mirroring(), Mirroring::Vertical);
+    assert!(header.battery());
+    assert!(!header.trainer());
+    assert_eq!(header.timing(), Timing::Pal);
+    assert_eq!(header.prg_rom_bytes(), 0x8000);
+    assert_eq!(header.chr_rom_bytes(), 0x2000);
+    assert_eq!(header.prg_ram_bytes(), 0);
+    assert_eq!(header.prg_nvram_bytes(), 0x2000);
+    assert_eq!(header.chr_ram_bytes(), 0);
+}
+
+#[test]
+fn nes2_header() {
+    let header = header(&[
+        0x02, 0x00, 0x4C, 0x4B, 0x35, 0x21, 0x70, 0x09, 0x03, 0x05, 0x00, 0x08,
+    ]);
+
+    assert_eq!(header.format(), HeaderFormat::Nes2);
+    assert_eq!(header.mapper(), 0x544);
+    assert_eq!(header.submapper(), 3);
+    assert_eq!(header.mirroring(), Mirroring::FourScreen);
+    assert!(header.trainer());
+    assert_eq!(header.console_type(), ConsoleType::Extended(5));
+    assert_eq!(header.timing(), Timing::Dendy);
+    assert_eq!(header.prg_rom_bytes(), 0x102 * 0x4000);
+    assert_eq!(header.chr_rom_bytes(), 0x200 * 0x2000);
+    assert_eq!(header.prg_ram_bytes(), 0);
+    assert_eq!(header.prg_nvram_bytes(), 0x2000);
+    assert_eq!(header.chr_ram_bytes(), 0x8000);
+    assert_eq!(header.chr_nvram_bytes(), 0);
+    assert_eq!(header.expansion_device(), ExpansionDevice::Zapper);
+}
+
+#[test]
+fn nes2_exponent_sizes() {
+    // 2^3 * 3 bytes of PRG-ROM and 2^10 * 1 bytes of CHR-ROM.
+    let header = header(&[0x0D, 0x28, 0x00, 0x08, 0x00, 0xFF]);
+
+    assert_eq!(header.prg_rom_bytes(), 24);
+    assert_eq!(header.chr_rom_bytes(), 1024);
+}
+
+#[test]
+fn diskdude_header() {
+    let header = header(&[
+        1, 1, 0x10, b'D', b'i', b's', b'k', b'D', b'u', b'd', b'e', b'!',
+    ]);
+
+    assert_eq!(header.format(), HeaderFormat::ArchaicInes);
+    assert_eq!(header.mapper(), 1);
+    assert_eq!(header.console_type(), ConsoleType::Nes);
+    assert_eq!(header.timing(), Timing::Ntsc);
+    assert_eq!(header.prg_ram_bytes(), 0x2000);
+}
+
+fn load_err(rom: Vec<u8>) -> CartridgeLoadError {
+    Cartridge::load(&mut rom.as_slice()).expect_err("cartridge should be rejected")
+}
+
+#[test]
+fn load_errors() {
+    let rom = ines(0, 0, 1, 1);
+
+    assert!(matches!(
+        load_err(rom[..10].to_vec()),
+        CartridgeLoadError::TruncatedHeader
+    ));
+
+    let mut bad_magic = rom.clone();
+    bad_magic[3] = 0;
+    assert!(matches!(load_err(bad_magic), CartridgeLoadError::BadMagic));
+
+    assert!(matches!(
+        load_err(ines(0, 0, 0, 1)),
+        CartridgeLoadError::InvalidSize
+    ));
+
+    assert!(matches!(
+        load_err(rom[..0x1000].to_vec()),
+        CartridgeLoadError::TruncatedPrg {
+            expected: 0x4000,
+            got: 0xFF0
+        }
+    ));
+
+    assert!(matches!(
+        load_err(rom[..rom.len() - 1].to_vec()),
+        CartridgeLoadError::TruncatedChr {
+            expected: 0x2000,
+            got: 0x1FFF
+        }
+    ));
+
+    // Only NES 2.0 headers are strict about trailing data.
+    let mut trailing = rom;
+    trailing.push(0);
+    load(trailing.clone());
+    trailing[7] |= 0x08;
+    assert!(matches!(
+        load_err(trailing),
+        CartridgeLoadError::TrailingData
+    ));
+
+    assert!(matches!(
+        load_err(ines(0xFF, 0, 1, 1)),
+        CartridgeLoadError::UnsupportedMapper(0xFF)
+    ));
+}
+
+#[test]
+fn nrom_128_is_mirrored() {
+    let mut rom = ines(0, 0, 1, 1);
+    rom[16 + 0x3FFC] = 0x34;
+    rom[16 + 0x3FFD] = 0x12;
+    let mut bus = load(rom);
+
+    assert_eq!(bus.read_word(0xBFFC), 0x1234);
+    assert_eq!(bus.read_word(0xFFFC), 0x1234);
+}
+
+#[test]
+fn nrom_256_is_not_mirrored() {
+    let mut bus = load(ines(0, 0, 2, 1));
+
+    assert_eq!(bus.read(0x8000), 0);
+    assert_eq!(bus.read(0xC000), 1);
+}
+
+/// Inserts a trainer filled with `val` into an iNES image.
+fn with_trainer(mut rom: Vec<u8>, val: u8) -> Vec<u8> {
+    rom[6] |= 0x04;
+    rom.splice(16..16, vec![val; 0x200]);
+    rom
+}
+
+#[test]
+fn trainer_on_boards_without_prg_ram() {
+    // UxROM, CNROM, AxROM, Color Dreams, BNROM and GxROM.
+    for &mapper in [2, 3, 7, 11, 34, 66].iter() {
+        let mut bus = load(with_trainer(ines(mapper, 0, 2, 1), 0x3C));
+        assert_eq!(bus.read(0x7000), 0x3C, "mapper {}", mapper);
+        assert_eq!(bus.read(0x71FF), 0x3C, "mapper {}", mapper);
+
+        bus.write(0x6000, 0x99);
+        assert_eq!(bus.read(0x6000), 0x99, "mapper {}", mapper);
+    }
+
+    // Without a trainer, nothing responds there.
+    let mut bus = load(ines(2, 0, 2, 1));
+    bus.write(0x0000, 0x11);
+    assert_eq!(bus.read(0x7000), 0x11);
+}
+
+#[test]
+fn trainer_is_mapped_at_7000() {
+    let mut rom = with_trainer(ines(0, 0, 1, 1), 0xA5);
+    rom[16 + 0x200] = 0x42;
+    let cart = Cartridge::load(&mut rom.as_slice()).unwrap();
+    assert_eq!(cart.trainer, vec![0xA5; 0x200]);
+
+    let mut bus = Bus::new(cart);
+    assert_eq!(bus.read(0x8000), 0x42);
+    assert_eq!(bus.read(0x6FFF), 0x00);
+    assert_eq!(bus.read(0x7000), 0xA5);
+    assert_eq!(bus.read(0x71FF), 0xA5);
+    assert_eq!(bus.read(0x7200), 0x00);
+
+    let mut bus = load(with_trainer(ines(1, 0, 2, 1), 0x5A));
+    assert_eq!(bus.read(0x7100), 0x5A);
+
+    let mut rom = with_trainer(ines(0, 0, 1, 1), 0);
+    rom.truncate(16 + 0x100);
+    assert!(matches!(
+        load_err(rom),
+        CartridgeLoadError::TruncatedTrainer
+    ));
+}
+
+#[test]
+fn nrom_prg_ram() {
+    let mut bus = load(ines(0, 0, 1, 1));
+
+    bus.write(0x6123, 0x42);
+    assert_eq!(bus.read(0x6123), 0x42);
+}
+
+#[test]
+fn nrom_chr_ram() {
+    let mut bus = load(ines(0, 0, 1, 0));
+
+    bus.write(0x2006, 0x01);
+    bus.write(0x2006, 0x00);
+    bus.write(0x2007, 0x99);
+    assert_eq!(read_vram(&mut bus, 0x0100), 0x99);
+}
+
+#[test]
+fn battery_backed_nvram() {
+    let mut cart = Cartridge::load(&mut ines(0, 0x02, 1, 1).as_slice()).unwrap();
+    assert!(cart.has_battery());
+    cart.set_nvram(&[1, 2, 3]);
+    assert_eq!(&cart.nvram()[..4], &[1, 2, 3, 0]);
+
+    let mut bus = Bus::new(cart);
+    assert_eq!(bus.read(0x6001), 2);
+
+    let cart = Cartridge::load(&mut ines(0, 0, 1, 1).as_slice()).unwrap();
+    assert!(cart.nvram().is_empty());
+}
+
+#[test]
+fn nvram_excludes_volatile_prg_ram() {
+    // NES 2.0 with 8 KiB of volatile PRG-RAM and 8 KiB of battery-backed PRG-RAM.
+    let mut rom = ines(0, 0x02, 1, 1);
+    rom[7] |= 0x08;
+    rom[10] = 0x77;
+    let mut bus = load(rom);
+    assert_eq!(bus.cartridge.prg_ram.len(), 0x4000);
+
+    // NROM only sees the volatile bank.
+    bus.write(0x6000, 0x55);
+    assert_eq!(bus.cartridge.nvram().len(), 0x2000);
+    assert!(bus.cartridge.nvram().iter().all(|&b| b == 0));
+
+    bus.cartridge.set_nvram(&[1, 2, 3]);
+    assert_eq!(&bus.cartridge.prg_ram[0x2000..0x2004], &[1, 2, 3, 0]);
+    assert_eq!(bus.read(0x6000), 0x55);
+}
+
+#[test]
+fn save_file_round_trip() {
+    let dir = std::env::temp_dir().join(format!("nesmu-save-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let rom = dir.join("game.nes");
+    std::fs::write(&rom, ines(0, 0x02, 1, 1)).unwrap();
+
+    let mut bus = Bus::new(Cartridge::open(&rom).unwrap());
+    bus.write(0x6010, 0x99);
+    bus.cartridge.flush_save().unwrap();
+    assert_eq!(std::fs::read(dir.join("game.sav")).unwrap()[0x10], 0x99);
+
+    bus.write(0x7FFF, 0x77);
+    drop(bus);
+
+    let mut bus = Bus::new(Cartridge::open(&rom).unwrap());
+    assert_eq!(bus.read(0x6010), 0x99);
+    assert_eq!(bus.read(0x7FFF), 0x77);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn nrom_mirroring() {
+    let mut bus = load(ines(0, 0x01, 1, 1));
+
+    bus.write(0x2006, 0x20);
+    bus.write(0x2006, 0x00);
+    bus.write(0x2007, 0x55);
+    assert_eq!(read_vram(&mut bus, 0x2800), 0x55);
+    assert_eq!(read_vram(&mut bus, 0x2400), 0x00);
+}
+
+#[test]
+fn four_screen_vram() {
+    let mut bus = load(ines(0, 0x08, 1, 1));
+
+    for (i, addr) in [0x2000, 0x2400, 0x2800, 0x2C00].iter().enumerate() {
+        bus.write(0x2006, (addr >> 8) as u8);
+        bus.write(0x2006, 0x00);
+        bus.write(0x2007, i as u8 + 1);
+    }
+    for (i, addr) in [0x2000, 0x2400, 0x2800, 0x2C00].iter().enumerate() {
+        assert_eq!(read_vram(&mut bus, *addr), i as u8 + 1);
+    }
+}
+
+/// Loads a value into a MMC1 register through the serial port.
+fn mmc1_write(bus: &mut Bus, addr: u16, val: u8) {
+    for bit in 0..5 {
+        bus.write(addr, val >> bit);
+        bus.clock_cartridge();
+        bus.clock_cartridge();
+    }
+}
+
+#[test]
+fn mmc1_prg_banking() {
+    let mut bus = load(ines(1, 0, 8, 2));
+
+    // The last bank is fixed at `$C000` after power up.
+    assert_eq!(bus.read(0xC000), 7);
+
+    mmc1_write(&mut bus, 0xE000, 3);
+    assert_eq!(bus.read(0x8000), 3);
+    assert_eq!(bus.read(0xC000), 7);
+
+    // Fix the first bank at `$8000`.
+    mmc1_write(&mut bus, 0x8000, 0x08);
+    assert_eq!(bus.read(0x8000), 0);
+    assert_eq!(bus.read(0xC000), 3);
+
+    // 32 KiB mode ignores the lowest bit.
+    mmc1_write(&mut bus, 0x8000, 0x00);
+    assert_eq!(bus.read(0x8000), 2);
+    assert_eq!(bus.read(0xC000), 3);
+}
+
+#[test]
+fn mmc1_mirroring_and_chr_banking() {
+    let mut bus = load(ines(1, 0, 2, 4));
+
+    // Vertical mirroring and 4 KiB CHR banks.
+    mmc1_write(&mut bus, 0x8000, 0x1E);
+    mmc1_write(&mut bus, 0xA000, 5);
+    mmc1_write(&mut bus, 0xC000, 2);
+    assert_eq!(read_vram(&mut bus, 0x0000), 2);
+    assert_eq!(read_vram(&mut bus, 0x1000), 1);
+
+    bus.write(0x2006, 0x20);
+    bus.write(0x2006, 0x00);
+    bus.write(0x2007, 0x55);
+    assert_eq!(read_vram(&mut bus, 0x2800), 0x55);
+
+    // Single screen mirroring.
+    mmc1_write(&mut bus, 0x8000, 0x00);
+    assert_eq!(read_vram(&mut bus, 0x2C00), 0x55);
+}
+
+#[test]
+fn mmc1_reset_and_consecutive_writes() {
+    let mut bus = load(ines(1, 0, 8, 2));
+
+    bus.write(0xE000, 0x01);
+    bus.clock_cartridge();
+    bus.clock_cartridge();
+    bus.write(0xE000, 0x80);
+    bus.clock_cartridge();
+    bus.clock_cartridge();
+
+    // The second write of each pair is on the next cycle and ignored.
+    for bit in 0..5 {
+        bus.write(0xE000, 5 >> bit);
+        bus.clock_cartridge();
+        bus.write(0xE000, 0);
+        bus.clock_cartridge();
+        bus.clock_cartridge();
+    }
+    assert_eq!(bus.read(0x8000), 5);
+}
+
+#[test]
+fn mmc1_prg_ram_disable() {
+    let mut bus = load(ines(1, 0, 2, 1));
+
+    bus.write(0x6000, 0x42);
+    assert_eq!(bus.read(0x6000), 0x42);
+
+    mmc1_write(&mut bus, 0xE000, 0x10);
+    // Disabled RAM doesn't drive the bus, so the last value is read back.
+    bus.write(0x0000, 0x37);
+    assert_eq!(bus.read(0x6000), 0x37);
+}
+
+#[test]
+fn mmc1_surom_outer_bank() {
+    let mut bus = load(ines(1, 0, 32, 0));
+
+    mmc1_write(&mut bus, 0xA000, 0x10);
+    mmc1_write(&mut bus, 0xE000, 2);
+    assert_eq!(bus.read(0x8000), 18);
+    assert_eq!(bus.read(0xC000), 31);
+
+    mmc1_write(&mut bus, 0xA000, 0x00);
+    assert_eq!(bus.read(0xC000), 15);
+}
+
+#[test]
+fn mmc1_4k_chr_mode_follows_a12() {
+    let mut bus = load(ines(1, 0, 32, 0));
+
+    // 4 KiB CHR mode, the outer bank is only selected for the upper pattern table.
+    mmc1_write(&mut bus, 0x8000, 0x1E);
+    mmc1_write(&mut bus, 0xA000, 0x00);
+    mmc1_write(&mut bus, 0xC000, 0x10);
+    assert_eq!(bus.read(0xC000), 15);
+    read_vram(&mut bus, 0x1000);
+    assert_eq!(bus.read(0xC000), 31);
+    read_vram(&mut bus, 0x0000);
+    assert_eq!(bus.read(0xC000), 15);
+
+    // NES 2.0 with 32 KiB of PRG-RAM, the upper pattern table selects bank 1.
+    let mut rom = ines(1, 0, 2, 0);
+    rom[7] |= 0x08;
+    rom[10] = 0x09;
+    rom[11] = 0x07;
+    let mut bus = load(rom);
+    mmc1_write(&mut bus, 0x8000, 0x1E);
+    mmc1_write(&mut bus, 0xC000, 0x04);
+    bus.write(0x6000, 0xAA);
+    read_vram(&mut bus, 0x1000);
+    bus.write(0x6000, 0xBB);
+    assert_eq!(bus.cartridge.prg_ram[0x0000], 0xAA);
+    assert_eq!(bus.cartridge.prg_ram[0x2000], 0xBB);
+    read_vram(&mut bus, 0x0000);
+    assert_eq!(bus.read(0x6000), 0xAA);
+}
+
+#[test]
+fn mmc1_sorom_prg_ram_bank() {
+    // SOROM: NES 2.0 with 8 KiB of volatile and 8 KiB of battery-backed PRG-RAM.
+    let mut rom = ines(1, 0x02, 2, 0);
+    rom[7] |= 0x08;
+    rom[10] = 0x77;
+    let mut bus = load(rom);
+
+    // Bit 2 does not select a bank on SOROM.
+    mmc1_write(&mut bus, 0xA000, 0x04);
+    bus.write(0x6000, 0x11);
+    assert_eq!(bus.cartridge.prg_ram[0x0000], 0x11);
+
+    // Bit 3 selects the battery-backed bank.
+    mmc1_write(&mut bus, 0xA000, 0x08);
+    bus.write(0x6000, 0x22);
+    assert_eq!(bus.cartridge.prg_ram[0x2000], 0x22);
+    assert_eq!(bus.cartridge.nvram()[0], 0x22);
+    assert_eq!(bus.cartridge.prg_ram[0x0000], 0x11);
+}
+
+#[test]
+fn mmc3_prg_banking() {
+    let mut bus = load(ines(4, 0, 8, 8));
+
+    bus.write(0x8000, 6);
+    bus.write(0x8001, 4);
+    bus.write(0x8000, 7);
+    bus.write(0x8001, 7);
+    assert_eq!(bus.read(0x8000), 2);
+    assert_eq!(bus.read(0xA000), 3);
+    assert_eq!(bus.read(0xC000), 7);
+    assert_eq!(bus.read(0xE000), 7);
+
+    // Swap `$8000` and `$C000`.
+    bus.write(0x8000, 0x46);
+    assert_eq!(bus.read(0x8000), 7);
+    assert_eq!(bus.read(0xC000), 2);
+}
+
+#[test]
+fn mmc3_chr_banking() {
+    let mut bus = load(ines(4, 0, 2, 8));
+
+    bus.write(0x8000, 0);
+    bus.write(0x8001, 16);
+    bus.write(0x8000, 5);
+    bus.write(0x8001, 63);
+    assert_eq!(read_vram(&mut bus, 0x0400), 2);
+    assert_eq!(read_vram(&mut bus, 0x1C00), 7);
+
+    // With inversion the 2 KiB banks are at `$1000`.
+    bus.write(0x8000, 0x80);
+    assert_eq!(read_vram(&mut bus, 0x1400), 2);
+    assert_eq!(read_vram(&mut bus, 0x0C00), 7);
+}
+
+#[test]
+fn mmc3_prg_ram_protect() {
+    let mut bus = load(ines(4, 0, 2, 1));
+
+    bus.write(0xA001, 0x80);
+    bus.write(0x6000, 0x42);
+    bus.write(0xA001, 0xC0);
+    bus.write(0x6000, 0x24);
+    assert_eq!(bus.read(0x6000), 0x42);
+
+    bus.write(0xA001, 0x00);
+    assert_eq!(bus.read(0x6000), 0);
+}
+
+#[test]
+fn mmc3_scanline_irq() {
+    let mut bus = load(ines(4, 0, 2, 1));
+
+    bus.write(0xC000, 10);
+    bus.write(0xC001, 0);
+    bus.write(0xE001, 0);
+
+    // Background at `$0000`, sprites at `$1000`.
+    bus.write(0x2000, 0x08);
+    bus.write(0x2001, 0x18);
+
+    while !bus.irq() {
+        for _ in 0..3 {
+            bus.clock_ppu();
+        }
+        bus.clock_cartridge();
+    }
+    assert_eq!(bus.ppu.scanline(), 10);
+
+    // Writing `$E000` acknowledges the interrupt.
+    bus.write(0xE000, 0);
+    assert!(!bus.irq());
+}
+
+#[test]
+fn uxrom_bus_conflicts() {
+    let mut bus = load(ines(2, 0, 8, 0));
+
+    // The fixed bank is filled with 7, so all bits of the value survive.
+    bus.write(0xC000, 3);
+    assert_eq!(bus.read(0x8000), 3);
+    assert_eq!(bus.read(0xC000), 7);
+
+    // The ROM at `$8000` now holds 3, which masks the written value.
+    bus.write(0x8000, 4);
+    assert_eq!(bus.read(0x8000), 0);
+}
+
+#[test]
+fn cnrom_chr_banking() {
+    let mut rom = ines(3, 0, 1, 4);
+    rom[16 + 0x1234] = 0xFF;
+    let mut bus = load(rom);
+
+    bus.write(0x9234, 2);
+    assert_eq!(read_vram(&mut bus, 0x1000), 2);
+
+    // The rest of the PRG-ROM is filled with 0, which masks every write.
+    bus.write(0x8000, 3);
+    assert_eq!(read_vram(&mut bus, 0x1000), 0);
+}
+
+#[test]
+fn axrom_single_screen() {
+    let mut bus = load(ines(7, 0, 8, 0));
+
+    bus.write(0x8000, 0x12);
+    assert_eq!(bus.read(0x8000), 4);
+
+    bus.write(0x2006, 0x20);
+    bus.write(0x2006, 0x00);
+    bus.write(0x2007, 0x55);
+    assert_eq!(read_vram(&mut bus, 0x2C00), 0x55);
+
+    bus.write(0x8000, 0x02);
+    assert_eq!(read_vram(&mut bus, 0x2C00), 0x00);
+}
+
+#[test]
+fn color_dreams_banking() {
+    let mut rom = ines(11, 0, 4, 4);
+    for b in rom[16..16 + 0x10000].iter_mut() {
+        *b = 0xFF;
+    }
+    let mut bus = load(rom);
+
+    bus.write(0x8000, 0x31);
+    assert_eq!(read_vram(&mut bus, 0x0000), 3);
+
+    bus.write(0x8000, 0x02);
+    bus.write(0x8000, 0xFF);
+    assert_eq!(read_vram(&mut bus, 0x0000), 3);
+}
+
+#[test]
+fn bnrom_prg_banking() {
+    let mut rom = ines(34, 0, 8, 0);
+    rom[16 + 0x7FFF] = 0xFF;
+    let mut bus = load(rom);
+
+    bus.write(0xFFFF, 0x03);
+    assert_eq!(bus.read(0x8000), 6);
+    assert_eq!(bus.read(0xC000), 7);
+}
+
+#[test]
+fn nina_001_banking() {
+    let mut bus = load(ines(34, 0, 4, 2));
+
+    bus.write(0x7FFD, 1);
+    bus.write(0x7FFE, 3);
+    bus.write(0x7FFF, 2);
+    assert_eq!(bus.read(0x8000), 2);
+    assert_eq!(read_vram(&mut bus, 0x0000), 1);
+    assert_eq!(read_vram(&mut bus, 0x1000), 1);
+    assert_eq!(bus.read(0x7FFE), 3);
+}
+
+#[test]
+fn mapper_34_submapper() {
+    // NINA-001 with only 8 KiB of CHR-ROM.
+    let mut rom = ines(34, 0, 4, 1);
+    rom[7] |= 0x08;
+    rom[8] = 0x10;
+    rom[10] = 0x07;
+    let mut bus = load(rom);
+    bus.write(0x7FFD, 1);
+    assert_eq!(bus.read(0x8000), 2);
+
+    // BNROM with more than 8 KiB of CHR-ROM.
+    let mut rom = ines(34, 0, 4, 2);
+    rom[7] |= 0x08;
+    rom[8] = 0x20;
+    rom[16 + 0x7FFF] = 0xFF;
+    let mut bus = load(rom);
+    bus.write(0x7FFD, 1);
+    assert_eq!(bus.read(0x8000), 0);
+    bus.write(0xFFFF, 1);
+    assert_eq!(bus.read(0x8000), 2);
+}
+
+#[test]
+fn gxrom_banking() {
+    let mut rom = ines(66, 0, 8, 4);
+    rom[16] = 0xFF;
+    let mut bus = load(rom);
+
+    bus.write(0x8000, 0x21);
+    assert_eq!(bus.read(0x8001), 4);
+    assert_eq!(bus.read(0xC000), 5);
+    assert_eq!(read_vram(&mut bus, 0x0000), 1);
+}
@@ -0,0 +1,32 @@
+//! Fixtures shared by the integration tests.
+// Every test binary only uses some of them.
+#![allow(dead_code)]
+
+use nesmu::{
+    cartridge::Cartridge,
+    mem::Memory,
+    nes::{Nes, Region},
+};
+
+/// Creates a console without a cartridge that runs `JMP $0000` from the internal RAM.
+pub fn idle_console(region: Region) -> Nes {
+    idle_console_with(Cartridge::default(), region)
+}
+
+/// Like [`idle_console`], with `cartridge` plugged in.
+pub fn idle_console_with(cartridge: Cartridge, region: Region) -> Nes {
+    let mut nes = Nes::with_region(cartridge, region);
+    for (addr, b) in [0x4C, 0x00, 0x00].iter().enumerate() {
+        nes.cpu.bus.write(addr as u16, *b);
+    }
+    nes.cpu.reg.pc = 0x0000;
+    nes
+}
+
+/// Starts a square wave at about 440 Hz on the first pulse channel.
+pub fn start_pulse(nes: &mut Nes) {
+    nes.cpu.bus.write(0x4015, 0x01);
+    nes.cpu.bus.write(0x4000, 0xBF);
+    nes.cpu.bus.write(0x4002, 0xFD);
+    nes.cpu.bus.write(0x4003, 0x00);
+}
@@ -0,0 +1,47 @@
+use nesmu::{
+    bus::Bus,
+    controller::{Button, Controller},
+    mem::Memory,
+};
+
+#[test]
+fn buttons_are_shifted_out() {
+    let mut controller = Controller::default();
+    controller.set_button(Button::A, true);
+    controller.set_button(Button::Start, true);
+    controller.set_button(Button::Right, true);
+
+    controller.write(1);
+    controller.write(0);
+    let bits: Vec<u8> = (0..10).map(|_| controller.read()).collect();
+    assert_eq!(bits, [1, 0, 0, 1, 0, 0, 0, 1, 1, 1]);
+}
+
+#[test]
+fn strobe_returns_a() {
+    let mut controller = Controller::default();
+    controller.set_button(Button::A, true);
+    controller.write(1);
+    assert_eq!(controller.read(), 1);
+    assert_eq!(controller.read(), 1);
+
+    controller.set_button(Button::A, false);
+    assert_eq!(controller.read(), 0);
+}
+
+#[test]
+fn ports_return_open_bus() {
+    let mut bus = Bus::default();
+    bus.controllers[1].set_button(Button::B, true);
+    bus.write(0x4016, 1);
+    bus.write(0x4016, 0);
+
+    // The upper bits are not driven and keep the high byte of the address,
+    // which is the last value on the bus for `LDA $4016`.
+    bus.write(0x0000, 0x40);
+    assert_eq!(bus.read(0x4016), 0x40);
+    bus.write(0x0000, 0x40);
+    assert_eq!(bus.read(0x4017), 0x40);
+    bus.write(0x0000, 0x40);
+    assert_eq!(bus.read(0x4017), 0x41);
+}
@@ -1,6 +1,10 @@
 use nesmu::{
     bus::Bus,
+    cartridge::Cartridge,
+    controller::Button,
     cpu::{Cpu, Registers},
+    mem::{DmaBus, FlatRam, Memory},
+    ppu::StatusFlag,
 };
 use std::fs::File;
 use std::io::{self, prelude::*, BufReader};
@@ -8,15 +12,10 @@ use std::io::{self, prelude::*, BufReader};
 const ROM_PATH: &str = "./tests/roms/nestest.nes";
 const LOG_PATH: &str = "./tests/roms/nestest.log";
 
-fn load_nestest() -> Result<Vec<u8>, io::Error> {
+fn load_nestest() -> Result<Cartridge, Box<dyn std::error::Error>> {
     let file = File::open(ROM_PATH)?;
     let mut read = BufReader::new(file);
-    let mut buf = Vec::new();
-    read.read_to_end(&mut buf)?;
-
-    let prg_size = buf[5] as usize * 16384;
-    let prg = &buf[16..=prg_size];
-    Ok(prg.to_vec())
+    Ok(Cartridge::load(&mut read)?)
 }
 
 fn load_nestest_log() -> Result<Vec<String>, io::Error> {
@@ -28,14 +27,14 @@ fn load_nestest_log() -> Result<Vec<String>, io::Error> {
         .collect())
 }
 
-fn parse_log_line(line: String) -> Result<(u32, Registers), Box<dyn std::error::Error>> {
+fn parse_log_line(line: String) -> Result<(u64, Registers), Box<dyn std::error::Error>> {
     let pc = u16::from_str_radix(&line[0..4], 16)?;
     let a = u8::from_str_radix(&line[50..=51], 16)?;
     let x = u8::from_str_radix(&line[55..=56], 16)?;
     let y = u8::from_str_radix(&line[60..=61], 16)?;
     let p = u8::from_str_radix(&line[65..=66], 16)?;
     let sp = u8::from_str_radix(&line[71..=72], 16)?;
-    let cycle = u32::from_str_radix(&line[90..line.len()], 10)?;
+    let cycle = line[90..line.len()].parse::<u64>()?;
     Ok((cycle, Registers { pc, a, x, y, p, sp }))
 }
 
@@ -43,35 +42,442 @@ fn parse_log_line(line: String) -> Result<(u32, Registers), Box<dyn std::error::
 fn nestest() {
     let rom = load_nestest().expect("Failed to read nestest file");
     let log = load_nestest_log().expect("Failed to read nestest log file");
-    let mut offset = 0xC000;
-    let mut ram = [0u8; 0x10000];
-
-    for b in rom.iter() {
-        ram[offset] = *b;
-        offset += 1;
-    }
 
-    let mut cpu = Cpu::default();
+    let mut cpu = Cpu::new(Bus::new(rom), Registers::default());
     cpu.reset();
+    cpu.execute_instruction();
+    // Start the automated mode right away.
     cpu.reg.pc = 0xC000;
-    cpu.reg.p = 36;
+    cpu.reg.p = 0x24;
 
-    let mut log = log.iter();
+    for line in log.iter() {
+        let (cycles, reg) = parse_log_line(line.to_string()).expect("failed to parse log line");
 
-    // let (cycles, reg) =
-    //     parse_log_line(line.unwrap().to_string()).expect("failed to parse log line");
+        assert_eq!(reg, cpu.reg, "registers differ at {}", line);
+        assert_eq!(cycles, cpu.cycle_count, "cycle count differs at {}", line);
 
-    // assert_eq!(reg, cpu.reg);
-    // assert_eq!(cycles, cpu.cycle_count);
-    // cpu.execute_instruction();
+        cpu.execute_instruction();
+    }
 
-    while let Some(line) = log.next() {
-        println!("Processing line {}", line);
-        let (cycles, reg) = parse_log_line(line.to_string()).expect("failed to parse log line");
+    // nestest stores the number of the first failed official and
+    // unofficial test in $02 and $03.
+    assert_eq!(cpu.bus.read(0x02), 0);
+    assert_eq!(cpu.bus.read(0x03), 0);
+}
+
+fn new_cpu(reg: Registers) -> Cpu<FlatRam> {
+    Cpu::new(FlatRam::default(), reg)
+}
 
+/// Runs `program` from `$0200` in RAM until all of it was executed.
+fn run<B: DmaBus>(mut cpu: Cpu<B>, program: &[u8]) -> Cpu<B> {
+    for (addr, b) in (0x0200..).zip(program) {
+        cpu.bus.write(addr, *b);
+    }
+
+    cpu.reg.pc = 0x0200;
+    while cpu.reg.pc < 0x0200 + program.len() as u16 {
         cpu.execute_instruction();
+    }
+    cpu
+}
+
+#[test]
+fn immediate_unofficial_opcodes() {
+    let reg = Registers {
+        a: 0xF0,
+        x: 0x3C,
+        p: 0x24,
+        ..Registers::default()
+    };
+
+    // ANC #$81
+    let cpu = run(new_cpu(reg.clone()), &[0x0B, 0x81]);
+    assert_eq!(cpu.reg.a, 0x80);
+    assert_eq!(cpu.reg.p, 0xA5);
+
+    // ALR #$33
+    let cpu = run(new_cpu(reg.clone()), &[0x4B, 0x33]);
+    assert_eq!(cpu.reg.a, 0x18);
+    assert_eq!(cpu.reg.p, 0x24);
+
+    // SEC, ARR #$C0
+    let cpu = run(new_cpu(reg.clone()), &[0x38, 0x6B, 0xC0]);
+    assert_eq!(cpu.reg.a, 0xE0);
+    assert_eq!(cpu.reg.p, 0xA5);
+
+    // AXS #$10
+    let cpu = run(new_cpu(reg), &[0xCB, 0x10]);
+    assert_eq!(cpu.reg.x, 0x20);
+    assert_eq!(cpu.reg.p, 0x25);
+    assert_eq!(cpu.cycle_count, 2);
+}
+
+#[test]
+fn high_byte_and_stores() {
+    let reg = Registers {
+        x: 0xFF,
+        y: 0x01,
+        ..Registers::default()
+    };
+
+    // SHX $0300,Y
+    let mut cpu = run(new_cpu(reg.clone()), &[0x9E, 0x00, 0x03]);
+    assert_eq!(cpu.bus.read(0x0301), 0x04);
+
+    // SHX $02F0,Y crosses into page 3, so the value becomes the high byte.
+    let reg = Registers {
+        x: 0x01,
+        y: 0x20,
+        ..reg
+    };
+    let mut cpu = run(new_cpu(reg), &[0x9E, 0xF0, 0x02]);
+    assert_eq!(cpu.bus.read(0x0310), 0x00);
+    assert_eq!(cpu.bus.read(0x0110), 0x01);
+}
+
+#[test]
+fn magic_constant() {
+    let reg = Registers {
+        a: 0x11,
+        x: 0x0F,
+        ..Registers::default()
+    };
+
+    // LXA #$FF
+    let cpu = run(new_cpu(reg.clone()), &[0xAB, 0xFF]);
+    assert_eq!(cpu.reg.a, 0xFF);
+    assert_eq!(cpu.reg.x, 0xFF);
+
+    // XAA #$FF
+    let mut xaa = new_cpu(reg);
+    xaa.magic = 0x00;
+    let xaa = run(xaa, &[0x8B, 0xFF]);
+    assert_eq!(xaa.reg.a, 0x01);
+}
+
+#[test]
+fn indexed_dummy_read() {
+    let reg = Registers {
+        x: 0x10,
+        ..Registers::default()
+    };
+    let mut cpu = Cpu::new(Bus::default(), reg);
+    cpu.bus.ppu.reg.set_status(StatusFlag::VerticalBlank, true);
+
+    // LDA $20F2,X reads $2002 before the carry is added, which clears
+    // the vblank flag before the real read from $2102.
+    let cpu = run(cpu, &[0xBD, 0xF2, 0x20]);
+    assert_eq!(cpu.reg.a & 0x80, 0x00);
+    assert_eq!(cpu.cycle_count, 5);
+}
+
+#[test]
+fn read_modify_write_timing() {
+    let mut cpu = new_cpu(Registers::default());
+    cpu.bus.write(0x0310, 0x41);
 
-        assert_eq!(reg, cpu.reg);
-        assert_eq!(cycles, cpu.cycle_count);
+    // ASL $0300,X
+    let reg = Registers {
+        x: 0x10,
+        ..Registers::default()
+    };
+    cpu.reg = reg;
+    let mut cpu = run(cpu, &[0x1E, 0x00, 0x03]);
+    assert_eq!(cpu.bus.read(0x0310), 0x82);
+    assert_eq!(cpu.cycle_count, 7);
+}
+
+/// Creates a CPU whose NMI handler is at `$0400` and whose IRQ handler is
+/// at `$0300`. The program and both handlers are filled with NOPs.
+fn with_vectors(reg: Registers) -> Cpu<FlatRam> {
+    let mut cpu = new_cpu(reg);
+    for addr in 0x0200..0x0500 {
+        cpu.bus.write(addr, 0xEA);
     }
+    cpu.bus.write_word(0xFFFA, 0x0400);
+    cpu.bus.write_word(0xFFFE, 0x0300);
+    cpu.reg.pc = 0x0200;
+    cpu
+}
+
+#[test]
+fn cli_delays_irq() {
+    let reg = Registers {
+        p: 0x24,
+        sp: 0xFD,
+        ..Registers::default()
+    };
+    let mut cpu = with_vectors(reg);
+    cpu.bus.write(0x0200, 0x58);
+    cpu.irq_line = true;
+
+    // CLI only takes effect after the instruction that follows it.
+    cpu.execute_instruction();
+    cpu.execute_instruction();
+    assert_eq!(cpu.reg.pc, 0x0202);
+    cpu.execute_instruction();
+    assert_eq!(cpu.reg.pc, 0x0300);
+    assert_eq!(cpu.bus.read(0x01FC), 0x02);
+}
+
+#[test]
+fn plp_delays_irq() {
+    let reg = Registers {
+        p: 0x24,
+        sp: 0xFC,
+        ..Registers::default()
+    };
+    let mut cpu = with_vectors(reg);
+    cpu.bus.write(0x0200, 0x28);
+    cpu.bus.write(0x01FD, 0x20);
+    cpu.irq_line = true;
+
+    // Like CLI, clearing the flag with PLP takes effect one instruction late.
+    cpu.execute_instruction();
+    cpu.execute_instruction();
+    assert_eq!(cpu.reg.pc, 0x0202);
+    cpu.execute_instruction();
+    assert_eq!(cpu.reg.pc, 0x0300);
+    assert_eq!(cpu.bus.read(0x01FC), 0x02);
+}
+
+#[test]
+fn irq_during_sei() {
+    let reg = Registers {
+        p: 0x20,
+        sp: 0xFD,
+        ..Registers::default()
+    };
+    let mut cpu = with_vectors(reg);
+    cpu.bus.write(0x0200, 0x78);
+
+    // The IRQ was polled before SEI set the flag, so it's still taken.
+    cpu.irq_line = true;
+    cpu.execute_instruction();
+    cpu.execute_instruction();
+    assert_eq!(cpu.reg.pc, 0x0300);
+    assert_eq!(cpu.bus.read(0x01FC), 0x01);
+}
+
+#[test]
+fn taken_branch_delays_irq() {
+    let reg = Registers {
+        p: 0x22,
+        sp: 0xFD,
+        ..Registers::default()
+    };
+    let mut cpu = with_vectors(reg);
+    cpu.bus.write(0x0200, 0xF0);
+    cpu.bus.write(0x0201, 0x00);
+
+    // BEQ +0 doesn't poll in its last cycle, so the NOP after it runs first.
+    cpu.clock();
+    cpu.irq_line = true;
+    cpu.execute_instruction();
+    cpu.execute_instruction();
+    assert_eq!(cpu.reg.pc, 0x0203);
+    cpu.execute_instruction();
+    assert_eq!(cpu.reg.pc, 0x0300);
+}
+
+#[test]
+fn nmi_hijacks_brk() {
+    let reg = Registers {
+        p: 0x24,
+        sp: 0xFD,
+        ..Registers::default()
+    };
+    let mut cpu = with_vectors(reg);
+    cpu.bus.write(0x0200, 0x00);
+
+    // The NMI shows up while BRK pushes the return address.
+    cpu.clock();
+    cpu.clock();
+    cpu.nmi_line = true;
+    cpu.execute_instruction();
+    assert_eq!(cpu.reg.pc, 0x0400);
+
+    // BRK still pushed its return address and the break flag.
+    assert_eq!(cpu.bus.read(0x01FC), 0x02);
+    assert_eq!(cpu.bus.read(0x01FB) & 0x10, 0x10);
+
+    // The NMI was handled, so the next instruction comes from the handler.
+    cpu.execute_instruction();
+    assert_eq!(cpu.reg.pc, 0x0401);
+}
+
+#[test]
+fn nmi_hijacks_irq() {
+    let reg = Registers {
+        p: 0x20,
+        sp: 0xFD,
+        ..Registers::default()
+    };
+    let mut cpu = with_vectors(reg);
+    cpu.irq_line = true;
+    cpu.execute_instruction();
+
+    // The NMI shows up while the IRQ sequence pushes the return address.
+    cpu.clock();
+    cpu.clock();
+    cpu.nmi_line = true;
+    cpu.execute_instruction();
+    assert_eq!(cpu.reg.pc, 0x0400);
+
+    // The IRQ's return address and status were pushed, without the break flag.
+    assert_eq!(cpu.bus.read(0x01FC), 0x01);
+    assert_eq!(cpu.bus.read(0x01FB) & 0x10, 0x00);
+
+    // The NMI was handled and the IRQ is masked now.
+    cpu.execute_instruction();
+    assert_eq!(cpu.reg.pc, 0x0401);
+}
+
+#[test]
+fn nmi_is_edge_triggered() {
+    let reg = Registers {
+        p: 0x24,
+        sp: 0xFD,
+        ..Registers::default()
+    };
+    let mut cpu = with_vectors(reg);
+    cpu.nmi_line = true;
+
+    cpu.execute_instruction();
+    cpu.execute_instruction();
+    assert_eq!(cpu.reg.pc, 0x0400);
+
+    // Holding the line doesn't trigger another NMI.
+    for _ in 0..4 {
+        cpu.execute_instruction();
+    }
+    assert_eq!(cpu.reg.pc, 0x0404);
+    assert_eq!(cpu.reg.sp, 0xFA);
+}
+
+#[test]
+fn dmc_dma_repeats_read() {
+    let mut cpu = Cpu::new(Bus::default(), Registers::default());
+    cpu.bus.controllers[0].set_button(Button::B, true);
+    cpu.bus.write(0x4016, 1);
+    cpu.bus.write(0x4016, 0);
+
+    // LDA $4016
+    for (addr, b) in (0x0200..).zip(&[0xAD, 0x16, 0x40]) {
+        cpu.bus.write(addr, *b);
+    }
+    cpu.reg.pc = 0x0200;
+
+    // The DMC asks for a sample right before the CPU reads the controller.
+    for _ in 0..3 {
+        cpu.clock();
+    }
+    cpu.bus.write(0x4015, 0x10);
+    cpu.execute_instruction();
+
+    assert!((7..=8).contains(&cpu.cycle_count), "{}", cpu.cycle_count);
+    assert_eq!(cpu.reg.a & 0x01, 0);
+    // The halted CPU read the controller again, so the B button was lost.
+    assert_eq!(cpu.bus.read(0x4016) & 0x01, 0);
+}
+
+#[test]
+fn dmc_dma_lines_up_with_apu() {
+    for &(apu_cycles, expected) in [(0, 7), (1, 8), (2, 7)].iter() {
+        let mut cpu = Cpu::new(Bus::default(), Registers::default());
+        for _ in 0..apu_cycles {
+            cpu.bus.clock_apu();
+        }
+        // A reset doesn't touch the APU, so the CPU's count has no say.
+        cpu.cycle_count = 1;
+
+        // LDA $0000
+        for (addr, b) in (0x0200..).zip(&[0xAD, 0x00, 0x00]) {
+            cpu.bus.write(addr, *b);
+        }
+        cpu.reg.pc = 0x0200;
+        for _ in 0..3 {
+            cpu.clock();
+        }
+        cpu.bus.write(0x4015, 0x10);
+        cpu.execute_instruction();
+        assert_eq!(cpu.cycle_count, 1 + expected, "{} APU cycles", apu_cycles);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    Read(u16),
+    Write(u16, u8),
+}
+
+/// RAM that logs every bus access of the CPU.
+#[derive(Default)]
+struct Recorder {
+    ram: FlatRam,
+    log: Vec<Access>,
+}
+
+impl Memory for Recorder {
+    fn read(&mut self, addr: u16) -> u8 {
+        self.log.push(Access::Read(addr));
+        self.ram.read(addr)
+    }
+
+    fn peek(&self, addr: u16) -> u8 {
+        self.ram.peek(addr)
+    }
+
+    fn write(&mut self, addr: u16, val: u8) {
+        self.log.push(Access::Write(addr, val));
+        self.ram.write(addr, val);
+    }
+}
+
+impl DmaBus for Recorder {}
+
+#[test]
+fn bus_access_sequence() {
+    use Access::*;
+
+    let reg = Registers {
+        x: 0x20,
+        ..Registers::default()
+    };
+    let mut cpu = Cpu::new(Recorder::default(), reg);
+    cpu.bus.write(0x0320, 0x41);
+    for (addr, b) in (0x0200..).zip(&[0x1E, 0x00, 0x03, 0xBD, 0xF0, 0x02]) {
+        cpu.bus.write(addr, *b);
+    }
+    cpu.reg.pc = 0x0200;
+    cpu.bus.log.clear();
+
+    // ASL $0300,X writes the old value back before the shifted one.
+    cpu.execute_instruction();
+    assert_eq!(
+        cpu.bus.log.drain(..).collect::<Vec<_>>(),
+        [
+            Read(0x0200),
+            Read(0x0201),
+            Read(0x0202),
+            Read(0x0320),
+            Read(0x0320),
+            Write(0x0320, 0x41),
+            Write(0x0320, 0x82),
+        ]
+    );
+
+    // LDA $02F0,X crosses a page and reads $0210 before the carry is added.
+    cpu.execute_instruction();
+    assert_eq!(
+        cpu.bus.log,
+        [
+            Read(0x0203),
+            Read(0x0204),
+            Read(0x0205),
+            Read(0x0210),
+            Read(0x0310),
+        ]
+    );
 }
@@ -0,0 +1,70 @@
+use nesmu::{
+    bus::Bus,
+    mem::{FlatRam, Memory, Ram},
+    ppu::StatusFlag,
+};
+
+#[test]
+fn word_access() {
+    let mut mem = FlatRam::default();
+    mem.write_word(0x1234, 0xBEEF);
+    assert_eq!(mem.read(0x1234), 0xEF);
+    assert_eq!(mem.read(0x1235), 0xBE);
+    assert_eq!(mem.read_word(0x1234), 0xBEEF);
+    assert_eq!(mem.peek_word(0x1234), 0xBEEF);
+
+    // Both word accesses wrap around at the end of the address space.
+    mem.write_word(0xFFFF, 0x1122);
+    assert_eq!(mem.read(0xFFFF), 0x22);
+    assert_eq!(mem.read(0x0000), 0x11);
+    assert_eq!(mem.read_word(0xFFFF), 0x1122);
+}
+
+#[test]
+fn page_wrapping_words() {
+    let mut mem = FlatRam::default();
+    mem.write(0x00FF, 0x34);
+    mem.write(0x0000, 0x12);
+    mem.write(0x0100, 0x56);
+    assert_eq!(mem.read_word_zero_page(0xFF), 0x1234);
+    assert_eq!(mem.read_word(0x00FF), 0x5634);
+
+    mem.write(0x10FF, 0x78);
+    mem.write(0x1000, 0x9A);
+    assert_eq!(mem.read_word_page_wrap(0x10FF), 0x9A78);
+}
+
+#[test]
+fn ram_is_mirrored() {
+    let mut ram = Ram::default();
+    ram.write(0x0042, 0x99);
+    assert_eq!(ram.peek(0x0842), 0x99);
+    assert_eq!(ram.read(0x1842), 0x99);
+}
+
+#[test]
+fn peek_has_no_side_effects() {
+    let mut bus = Bus::default();
+    bus.ppu.reg.set_status(StatusFlag::VerticalBlank, true);
+
+    assert_eq!(bus.peek(0x2002) & 0x80, 0x80);
+    assert_eq!(bus.peek(0x2002) & 0x80, 0x80);
+    assert_eq!(bus.read(0x2002) & 0x80, 0x80);
+    assert_eq!(bus.peek(0x2002) & 0x80, 0x00);
+}
+
+#[test]
+fn unmapped_reads_return_open_bus() {
+    let mut bus = Bus::default();
+
+    bus.write(0x0010, 0x5A);
+    assert_eq!(bus.read(0x0010), 0x5A);
+    assert_eq!(bus.read(0x4018), 0x5A);
+    assert_eq!(bus.peek(0x5000), 0x5A);
+    assert_eq!(bus.read(0x5000), 0x5A);
+
+    bus.write(0x0010, 0xA5);
+    bus.read(0x0010);
+    assert_eq!(bus.read(0x401F), 0xA5);
+    assert_eq!(bus.open_bus(), 0xA5);
+}
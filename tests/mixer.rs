@@ -0,0 +1,159 @@
+mod common;
+
+use common::{idle_console, start_pulse};
+use nesmu::{
+    apu::{Apu, Channel},
+    blip::BlipBuffer,
+    mixer::{ChannelMix, Mixer},
+    nes::Region,
+};
+
+#[test]
+fn blip_step_settles() {
+    let mut blip = BlipBuffer::new(1_000_000.0, 48000.0);
+    blip.add_delta(1.0);
+    for _ in 0..10_000 {
+        blip.clock();
+    }
+    assert_eq!(blip.available(), 480);
+
+    let mut samples = Vec::new();
+    assert_eq!(blip.read(1000, |s| samples.push(s)), 480);
+    assert_eq!(blip.available(), 0);
+
+    // The step is smeared over a few samples, with little ringing.
+    assert!(samples[..4].iter().all(|s| s.abs() < 0.01));
+    assert!(samples[20..].iter().all(|s| (s - 1.0).abs() < 0.001));
+    assert!(samples.iter().all(|s| *s < 1.1));
+}
+
+#[test]
+fn silent_apu() {
+    let apu = Apu::default();
+    let mut mixer = Mixer::new(Region::Ntsc.cpu_clock_rate(), 48000);
+    // The triangle rests at its highest level.
+    assert!(mixer.mix(&apu) > 0.0);
+
+    let mut out = [1.0; 10_000];
+    let mut count = 0;
+    for _ in 0..1_000_000 {
+        mixer.clock(&apu);
+        count += mixer.read_samples(&mut out[count..]);
+    }
+    assert_eq!(count, 10_000);
+
+    // The high-pass filters remove the constant level.
+    assert!(out[..100].iter().any(|s| *s > 0.1));
+    assert!(out[9000..].iter().all(|s| s.abs() < 0.001));
+}
+
+#[test]
+fn drain_audio() {
+    let mut nes = idle_console(Region::Ntsc);
+    start_pulse(&mut nes);
+
+    // The first frame after power on is cut short.
+    nes.run_frame().unwrap();
+    let mut out = [0.0; 2048];
+    nes.drain_audio(&mut out);
+
+    nes.run_frame().unwrap();
+    let count = nes.drain_audio(&mut out);
+    assert!((730..=740).contains(&count), "{}", count);
+    assert_eq!(nes.drain_audio(&mut out), 0);
+
+    let samples = &out[..count];
+    assert!(samples.iter().all(|s| (-1.0..=1.0).contains(s)));
+    let rms = (samples.iter().map(|s| s * s).sum::<f32>() / count as f32).sqrt();
+    assert!(rms > 0.05, "{}", rms);
+
+    let mut out = [0; 2048];
+    nes.run_frame().unwrap();
+    let count = nes.drain_audio_i16(&mut out);
+    assert!(out[..count].iter().any(|s| *s > 1000));
+    assert!(out[..count].iter().any(|s| *s < -1000));
+}
+
+#[test]
+fn sample_rate() {
+    let mut nes = idle_console(Region::Ntsc);
+    nes.set_sample_rate(48000);
+    assert_eq!(nes.sample_rate(), 48000);
+
+    nes.run_frame().unwrap();
+    let first = nes.audio_available();
+    nes.run_frame().unwrap();
+    let second = nes.audio_available() - first;
+    assert!((795..=805).contains(&second), "{}", second);
+
+    // Only one second is kept when nobody drains the samples.
+    for _ in 0..70 {
+        nes.run_frame().unwrap();
+    }
+    assert_eq!(nes.audio_available(), 48000);
+}
+
+#[test]
+fn channel_gain() {
+    let mut mix = ChannelMix::default();
+    assert!(Channel::ALL.iter().all(|c| mix.gain(*c) == 1.0));
+
+    mix.set_volume(Channel::Noise, 0.5);
+    mix.set_muted(Channel::Pulse2, true);
+    assert_eq!(mix.gain(Channel::Noise), 0.5);
+    assert_eq!(mix.gain(Channel::Pulse2), 0.0);
+
+    // Soloing silences everything else, but muting still wins.
+    mix.set_solo(Channel::Noise, true);
+    mix.set_solo(Channel::Pulse2, true);
+    assert_eq!(mix.gain(Channel::Pulse1), 0.0);
+    assert_eq!(mix.gain(Channel::Noise), 0.5);
+    assert_eq!(mix.gain(Channel::Pulse2), 0.0);
+
+    mix.set_solo(Channel::Noise, false);
+    mix.set_solo(Channel::Pulse2, false);
+    assert_eq!(mix.gain(Channel::Pulse1), 1.0);
+}
+
+#[test]
+fn mix_follows_channel_settings() {
+    let apu = Apu::default();
+    let mut mixer = Mixer::new(Region::Ntsc.cpu_clock_rate(), 48000);
+    let full = mixer.mix(&apu);
+
+    mixer.channels.set_volume(Channel::Triangle, 0.5);
+    let half = mixer.mix(&apu);
+    assert!(half > 0.0 && half < full);
+
+    mixer.channels.set_solo(Channel::Pulse1, true);
+    assert_eq!(mixer.mix(&apu), 0.0);
+}
+
+#[test]
+fn muting_keeps_emulation_state() {
+    let mut consoles = [idle_console(Region::Ntsc), idle_console(Region::Ntsc)];
+    for &channel in Channel::ALL.iter() {
+        consoles[1].channel_mix_mut().set_muted(channel, true);
+    }
+
+    let mut out = [[0.0; 2048]; 2];
+    let mut counts = [0; 2];
+    for (i, nes) in consoles.iter_mut().enumerate() {
+        start_pulse(nes);
+        for _ in 0..2 {
+            nes.run_frame().unwrap();
+            counts[i] = nes.drain_audio(&mut out[i]);
+        }
+    }
+
+    let [a, b] = &consoles;
+    assert_eq!(a.cpu.cycle_count, b.cpu.cycle_count);
+    assert_eq!(a.cpu.bus.apu.peek_status(), b.cpu.bus.apu.peek_status());
+    for &channel in Channel::ALL.iter() {
+        assert_eq!(a.cpu.bus.apu.output(channel), b.cpu.bus.apu.output(channel));
+    }
+
+    assert_eq!(counts[0], counts[1]);
+    assert!(out[0][..counts[0]].iter().any(|s| s.abs() > 0.05));
+    assert!(out[1][..counts[1]].iter().all(|s| *s == 0.0));
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::{idle_console, idle_console_with};
+use nesmu::{
+    cartridge::Cartridge,
+    cpu::Jam,
+    mem::Memory,
+    nes::{Nes, Region},
+};
+
+fn cycles_per_frame(region: Region) -> u64 {
+    let mut nes = idle_console(region);
+    nes.run_frame().unwrap();
+    let start = nes.cpu.cycle_count;
+    nes.run_frame().unwrap();
+    nes.cpu.cycle_count - start
+}
+
+#[test]
+fn ntsc_frame_timing() {
+    // 341 * 262 dots at three dots per CPU cycle, give or take one instruction.
+    let cycles = cycles_per_frame(Region::Ntsc);
+    assert!((29778..=29784).contains(&cycles), "{}", cycles);
+}
+
+#[test]
+fn pal_frame_timing() {
+    // 341 * 312 dots at 3.2 dots per CPU cycle, give or take one instruction.
+    let cycles = cycles_per_frame(Region::Pal);
+    assert!((33245..=33251).contains(&cycles), "{}", cycles);
+}
+
+#[test]
+fn step_instruction() {
+    let mut nes = idle_console(Region::Ntsc);
+
+    nes.step_instruction().unwrap();
+    assert_eq!(nes.cpu.reg.pc, 0x0000);
+    assert_eq!(nes.cpu.cycle_count, 3);
+    assert_eq!(nes.cpu.bus.ppu.dot(), 9);
+}
+
+#[test]
+fn jam_halts_until_reset() {
+    let mut nes = Nes::new(Cartridge::default());
+    for (addr, b) in [0xEA, 0x22, 0xEA].iter().enumerate() {
+        nes.cpu.bus.write(addr as u16, *b);
+    }
+    nes.cpu.reg.pc = 0x0000;
+
+    nes.step_instruction().unwrap();
+    let jam = nes.step_instruction().unwrap_err();
+    assert_eq!(
+        jam,
+        Jam {
+            pc: 0x0001,
+            opcode: 0x22
+        }
+    );
+
+    // The rest of the console keeps running.
+    let dot = nes.cpu.bus.ppu.dot();
+    assert_eq!(nes.run_frame(), Err(jam));
+    assert_eq!(nes.cpu.reg.pc, 0x0002);
+    assert_ne!(nes.cpu.bus.ppu.dot(), dot);
+
+    nes.reset();
+    assert_eq!(nes.cpu.halted, None);
+}
+
+#[test]
+fn periodic_save_keeps_error() {
+    // NROM with battery-backed PRG-RAM.
+    let mut rom = vec![b'N', b'E', b'S', 0x1A, 1, 1, 0x02];
+    rom.resize(16 + 0x4000 + 0x2000, 0);
+    let mut cart = Cartridge::load(&mut rom.as_slice()).unwrap();
+    let dir = std::env::temp_dir().join(format!("nesmu-missing-{}", std::process::id()));
+    cart.attach_save_file(dir.join("game.sav")).unwrap();
+
+    let mut nes = idle_console_with(cart, Region::Ntsc);
+    nes.cpu.bus.write(0x6000, 0x01);
+    for _ in 0..60 {
+        nes.run_frame().unwrap();
+    }
+    assert!(nes.last_save_error().is_some());
+
+    std::fs::create_dir_all(&dir).unwrap();
+    for _ in 0..60 {
+        nes.run_frame().unwrap();
+    }
+    assert!(nes.last_save_error().is_none());
+    assert_eq!(std::fs::read(dir.join("game.sav")).unwrap()[0], 0x01);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
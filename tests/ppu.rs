@@ -0,0 +1,190 @@
+use nesmu::{
+    bus::Bus,
+    cartridge::Cartridge,
+    mem::Memory,
+    ppu::{Ppu, StatusFlag, SCREEN_HEIGHT, SCREEN_WIDTH},
+};
+
+fn set_vram_addr(bus: &mut Bus, addr: u16) {
+    bus.write(0x2006, (addr >> 8) as u8);
+    bus.write(0x2006, addr as u8);
+}
+
+#[test]
+fn ppudata_read_is_buffered() {
+    let mut bus = Bus::default();
+
+    set_vram_addr(&mut bus, 0x2000);
+    bus.write(0x2007, 0x11);
+    bus.write(0x2007, 0x22);
+
+    set_vram_addr(&mut bus, 0x2000);
+    bus.read(0x2007);
+    assert_eq!(bus.read(0x2007), 0x11);
+    assert_eq!(bus.read(0x2007), 0x22);
+}
+
+#[test]
+fn ppudata_increments_by_32() {
+    let mut bus = Bus::default();
+
+    bus.write(0x2000, 0x04);
+    set_vram_addr(&mut bus, 0x2000);
+    bus.write(0x2007, 0xAB);
+    bus.write(0x2007, 0xCD);
+
+    bus.write(0x2000, 0x00);
+    set_vram_addr(&mut bus, 0x2020);
+    bus.read(0x2007);
+    assert_eq!(bus.read(0x2007), 0xCD);
+}
+
+#[test]
+fn palette_is_not_buffered_and_mirrored() {
+    let mut bus = Bus::default();
+
+    set_vram_addr(&mut bus, 0x3F10);
+    bus.write(0x2007, 0x2A);
+
+    set_vram_addr(&mut bus, 0x3F00);
+    assert_eq!(bus.read(0x2007), 0x2A);
+}
+
+#[test]
+fn registers_are_mirrored() {
+    let mut bus = Bus::default();
+
+    bus.write(0x3FFE, 0x20);
+    bus.write(0x3FFE, 0x00);
+    bus.write(0x3FFF, 0x5A);
+
+    set_vram_addr(&mut bus, 0x2000);
+    bus.read(0x2007);
+    assert_eq!(bus.read(0x2FFF), 0x5A);
+}
+
+#[test]
+fn status_read_clears_vblank_and_toggle() {
+    let mut bus = Bus::default();
+    bus.ppu.reg.set_status(StatusFlag::VerticalBlank, true);
+
+    bus.write(0x2006, 0x21);
+    assert_eq!(bus.read(0x2002) & 0x80, 0x80);
+    assert_eq!(bus.read(0x2002) & 0x80, 0x00);
+
+    // The toggle was reset, so this is a full address again.
+    set_vram_addr(&mut bus, 0x2100);
+    bus.write(0x2007, 0x77);
+    set_vram_addr(&mut bus, 0x2100);
+    bus.read(0x2007);
+    assert_eq!(bus.read(0x2007), 0x77);
+}
+
+/// A cartridge whose tile 1 is filled with color 1 and tile 2 with color 3.
+fn pattern_cartridge() -> Cartridge {
+    let mut chr_rom = vec![0u8; 0x2000];
+    chr_rom[0x10..0x18].copy_from_slice(&[0xFF; 8]);
+    chr_rom[0x20..0x30].copy_from_slice(&[0xFF; 16]);
+    let mut cart = Cartridge::default();
+    cart.chr_rom = chr_rom;
+    cart
+}
+
+fn write_ppu(ppu: &mut Ppu, cart: &mut Cartridge, addr: u16, data: &[u8]) {
+    ppu.write_register(0x2006, (addr >> 8) as u8, cart);
+    ppu.write_register(0x2006, addr as u8, cart);
+    for b in data {
+        ppu.write_register(0x2007, *b, cart);
+    }
+}
+
+fn run_frame(ppu: &mut Ppu, cart: &mut Cartridge) {
+    let frame = ppu.frame_count();
+    while ppu.frame_count() == frame {
+        ppu.clock(cart);
+    }
+}
+
+#[test]
+fn renders_background_tiles() {
+    let mut cart = pattern_cartridge();
+    let mut ppu = Ppu::default();
+
+    write_ppu(&mut ppu, &mut cart, 0x3F00, &[0x0F, 0x16]);
+    write_ppu(&mut ppu, &mut cart, 0x2021, &[0x01]);
+    ppu.write_register(0x2005, 0, &mut cart);
+    ppu.write_register(0x2005, 0, &mut cart);
+    ppu.write_register(0x2001, 0x0A, &mut cart);
+
+    run_frame(&mut ppu, &mut cart);
+    run_frame(&mut ppu, &mut cart);
+
+    let frame = ppu.frame();
+    assert_eq!(frame.len(), SCREEN_WIDTH * SCREEN_HEIGHT);
+    assert_eq!(frame[8 * SCREEN_WIDTH + 8], 0x16);
+    assert_eq!(frame[15 * SCREEN_WIDTH + 15], 0x16);
+    assert_eq!(frame[8 * SCREEN_WIDTH + 16], 0x0F);
+    assert_eq!(frame[7 * SCREEN_WIDTH + 8], 0x0F);
+}
+
+#[test]
+fn sprite_zero_hit() {
+    let mut cart = pattern_cartridge();
+    let mut ppu = Ppu::default();
+
+    write_ppu(&mut ppu, &mut cart, 0x3F00, &[0x0F, 0x01, 0x02, 0x03]);
+    write_ppu(&mut ppu, &mut cart, 0x3F10, &[0x0F, 0x11, 0x12, 0x13]);
+    write_ppu(&mut ppu, &mut cart, 0x2000 + 4 * 32 + 4, &[0x01]);
+    ppu.write_register(0x2003, 0, &mut cart);
+    for b in [31, 0x02, 0x00, 36].iter().chain(&[0xFF; 252]) {
+        ppu.write_register(0x2004, *b, &mut cart);
+    }
+    ppu.write_register(0x2005, 0, &mut cart);
+    ppu.write_register(0x2005, 0, &mut cart);
+    ppu.write_register(0x2001, 0x1E, &mut cart);
+
+    run_frame(&mut ppu, &mut cart);
+    run_frame(&mut ppu, &mut cart);
+    assert!(ppu.reg.get_status(StatusFlag::SpriteZeroHit));
+    assert!(!ppu.reg.get_status(StatusFlag::SpriteOverflow));
+}
+
+#[test]
+fn sprite_overflow() {
+    let mut cart = pattern_cartridge();
+    let mut ppu = Ppu::default();
+
+    ppu.write_register(0x2003, 0, &mut cart);
+    for i in 0..=255u8 {
+        let val = if i % 4 == 0 && i < 36 { 50 } else { 0xF8 };
+        ppu.write_register(0x2004, val, &mut cart);
+    }
+    ppu.write_register(0x2001, 0x18, &mut cart);
+
+    run_frame(&mut ppu, &mut cart);
+    assert!(ppu.reg.get_status(StatusFlag::SpriteOverflow));
+}
+
+#[test]
+fn io_latch_decays() {
+    let mut ppu = Ppu::default();
+    let mut cart = Cartridge::default();
+
+    // Writes to any register fill the latch, which shows up in the
+    // lower bits of PPUSTATUS.
+    ppu.write_register(0x2002, 0x5F, &mut cart);
+    assert_eq!(ppu.read_register(0x2002, &mut cart) & 0x1F, 0x1F);
+    // The read drove the upper bits with the flags, which are clear.
+    assert_eq!(ppu.read_register(0x2005, &mut cart), 0x1F);
+
+    for _ in 0..10 {
+        run_frame(&mut ppu, &mut cart);
+    }
+    assert_eq!(ppu.peek_register(0x2002) & 0x1F, 0x1F);
+
+    // Reading PPUSTATUS doesn't refresh the undriven bits.
+    for _ in 0..30 {
+        run_frame(&mut ppu, &mut cart);
+    }
+    assert_eq!(ppu.read_register(0x2002, &mut cart) & 0x1F, 0x00);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{idle_console, start_pulse};
+use nesmu::{nes::Region, wav::WavWriter};
+use std::io::Cursor;
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// The samples of a WAV file that was written by `WavWriter`.
+fn samples(wav: &[u8]) -> Vec<i16> {
+    wav[44..]
+        .chunks(2)
+        .map(|s| i16::from_le_bytes([s[0], s[1]]))
+        .collect()
+}
+
+#[test]
+fn wav_header() {
+    let mut wav = WavWriter::new(Cursor::new(Vec::new()), 48000).unwrap();
+    wav.write_samples(&[0.0, 1.0, -1.0, 2.0]).unwrap();
+    let wav = wav.finish().unwrap().into_inner();
+
+    assert_eq!(wav.len(), 52);
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(le_u32(&wav[4..]), 44);
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(le_u32(&wav[24..]), 48000);
+    assert_eq!(le_u32(&wav[28..]), 96000);
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(le_u32(&wav[40..]), 8);
+    assert_eq!(samples(&wav), [0, i16::MAX, -i16::MAX, i16::MAX]);
+}
+
+#[test]
+fn record_stems() {
+    let dir = std::env::temp_dir().join(format!("nesmu-wav-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut nes = idle_console(Region::Ntsc);
+    start_pulse(&mut nes);
+    nes.run_frame().unwrap();
+
+    nes.record_wav(dir.join("song.wav"), 10, true).unwrap();
+
+    let mixed = std::fs::read(dir.join("song.wav")).unwrap();
+    let len = mixed.len();
+    assert!((7300..=7400).contains(&samples(&mixed).len()));
+    for name in ["pulse1", "pulse2", "triangle", "noise", "dmc"].iter() {
+        let stem = std::fs::read(dir.join(format!("song-{}.wav", name))).unwrap();
+        assert_eq!(stem.len(), len, "{}", name);
+    }
+
+    let pulse1 = std::fs::read(dir.join("song-pulse1.wav")).unwrap();
+    assert!(samples(&pulse1).iter().any(|s| s.abs() > 1000));
+    let pulse2 = std::fs::read(dir.join("song-pulse2.wav")).unwrap();
+    assert!(samples(&pulse2).iter().all(|s| *s == 0));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}